@@ -1,287 +1,2082 @@
-use rusqlite::{Connection, Result as SqliteResult};
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{Connection, OptionalExtension, Result as SqliteResult, Row};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Mutex;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Weekday};
 use uuid::Uuid;
+use crate::error::{AppError, AppResult};
+use crate::migrations;
 use crate::models::*;
+use crate::recurrence;
+use crate::stats;
+
+const TASK_GROUP_COLUMNS: &str = "id, name, description, created_at, updated_at, archived_at, deleted_at, sort_order, parent_id, color, icon, default_duration_minutes, default_timer_mode";
+const TASK_COLUMNS: &str = "id, task_group_id, name, description, duration_minutes, pomodoro_work_minutes, pomodoro_short_break_minutes, pomodoro_long_break_minutes, pomodoro_long_break_every, created_at, updated_at, archived_at, deleted_at, status, priority, due_date, completed_at, estimate_minutes, recurrence_rule, recurrence_days, recurrence_interval_days, recurrence_day_of_month, recurrence_start_date, recurrence_target_minutes, last_occurrence_date, sort_order";
+const TAG_COLUMNS: &str = "id, name, created_at, updated_at";
+const SUBTASK_COLUMNS: &str = "id, task_id, title, position, completed_at, created_at, updated_at";
+const GOAL_COLUMNS: &str = "id, name, scope, task_id, task_group_id, tag_id, period, days_of_week, target_minutes, created_at, updated_at, timezone";
+const SESSION_COLUMNS: &str = "id, task_id, start_time, end_time, duration_minutes, duration_seconds, completed, outcome, kind, planned_minutes, is_paused, paused_at, total_paused_duration_ms, needs_review, is_manual, created_at";
+
+const DEFAULT_PAGE_SIZE: u32 = 50;
+const MAX_PAGE_SIZE: u32 = 200;
+
+// 崩溃恢复时正向计时会话最多记录的时长
+pub const RECOVERED_SESSION_CAP_MINUTES: i64 = 240;
+
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_DESCRIPTION_LENGTH: usize = 2000;
+const MAX_COLOR_LENGTH: usize = 64;
+const MAX_ICON_LENGTH: usize = 16;
+
+const DEFAULT_GOAL_HISTORY: u32 = 7;
+const MAX_GOAL_HISTORY: u32 = 366;
+
+fn column_count(columns: &str) -> usize {
+    columns.split(',').count()
+}
+
+// "id, name" -> "tg.id, tg.name"，用于 JOIN 查询
+fn prefixed(alias: &str, columns: &str) -> String {
+    columns
+        .split(',')
+        .map(|column| format!("{}.{}", alias, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// 时间列格式损坏时返回转换错误，而不是让整个命令 panic
+fn parse_time(idx: usize, value: &str) -> SqliteResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+fn get_time(row: &Row, idx: usize) -> SqliteResult<DateTime<Utc>> {
+    parse_time(idx, &row.get::<_, String>(idx)?)
+}
+
+fn get_optional_time(row: &Row, idx: usize) -> SqliteResult<Option<DateTime<Utc>>> {
+    row.get::<_, Option<String>>(idx)?
+        .map(|value| parse_time(idx, &value))
+        .transpose()
+}
+
+fn get_optional_date(row: &Row, idx: usize) -> SqliteResult<Option<NaiveDate>> {
+    row.get::<_, Option<String>>(idx)?
+        .map(|value| {
+            NaiveDate::parse_from_str(&value, "%Y-%m-%d")
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+        })
+        .transpose()
+}
+
+// 枚举列中出现未知的值说明数据已损坏，不能猜成某个默认值
+fn get_enum<T>(row: &Row, idx: usize, parse: fn(&str) -> Option<T>) -> SqliteResult<T> {
+    let value = row.get::<_, String>(idx)?;
+    parse(&value).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, format!("unknown value: {}", value).into())
+    })
+}
+
+fn get_optional_enum<T>(row: &Row, idx: usize, parse: fn(&str) -> Option<T>) -> SqliteResult<Option<T>> {
+    match row.get::<_, Option<String>>(idx)? {
+        Some(_) => get_enum(row, idx, parse).map(Some),
+        None => Ok(None),
+    }
+}
+
+// 星期掩码只使用低 7 位，至少要选中一天
+fn get_days_of_week(row: &Row, idx: usize) -> SqliteResult<Option<Vec<Weekday>>> {
+    match row.get::<_, Option<i32>>(idx)? {
+        Some(mask) if !(1..=0x7f).contains(&mask) => Err(rusqlite::Error::FromSqlConversionFailure(
+            idx,
+            rusqlite::types::Type::Integer,
+            format!("invalid days_of_week mask: {}", mask).into(),
+        )),
+        mask => Ok(mask.map(recurrence::mask_to_days)),
+    }
+}
+
+fn map_task_group(row: &Row, offset: usize) -> SqliteResult<TaskGroup> {
+    Ok(TaskGroup {
+        id: row.get(offset)?,
+        name: row.get(offset + 1)?,
+        description: row.get(offset + 2)?,
+        created_at: get_time(row, offset + 3)?,
+        updated_at: get_time(row, offset + 4)?,
+        archived_at: get_optional_time(row, offset + 5)?,
+        deleted_at: get_optional_time(row, offset + 6)?,
+        sort_order: row.get(offset + 7)?,
+        parent_id: row.get(offset + 8)?,
+        color: row.get(offset + 9)?,
+        icon: row.get(offset + 10)?,
+        default_duration_minutes: row.get(offset + 11)?,
+        default_timer_mode: get_optional_enum(row, offset + 12, TimerMode::parse)?,
+    })
+}
+
+fn map_task(row: &Row, offset: usize) -> SqliteResult<Task> {
+    Ok(Task {
+        id: row.get(offset)?,
+        task_group_id: row.get(offset + 1)?,
+        name: row.get(offset + 2)?,
+        description: row.get(offset + 3)?,
+        duration_minutes: row.get(offset + 4)?,
+        pomodoro: match row.get::<_, Option<i32>>(offset + 5)? {
+            Some(work_minutes) => Some(PomodoroConfig {
+                work_minutes,
+                short_break_minutes: row.get(offset + 6)?,
+                long_break_minutes: row.get(offset + 7)?,
+                long_break_every: row.get(offset + 8)?,
+            }),
+            None => None,
+        },
+        created_at: get_time(row, offset + 9)?,
+        updated_at: get_time(row, offset + 10)?,
+        archived_at: get_optional_time(row, offset + 11)?,
+        deleted_at: get_optional_time(row, offset + 12)?,
+        status: get_enum(row, offset + 13, TaskStatus::parse)?,
+        priority: get_enum(row, offset + 14, TaskPriority::parse)?,
+        due_date: get_optional_date(row, offset + 15)?,
+        completed_at: get_optional_time(row, offset + 16)?,
+        estimate_minutes: row.get(offset + 17)?,
+        recurrence: match get_optional_enum(row, offset + 18, RecurrenceRule::parse)? {
+            Some(rule) => Some(Recurrence {
+                rule,
+                days_of_week: row.get::<_, Option<i32>>(offset + 19)?.map(recurrence::mask_to_days),
+                interval_days: row.get(offset + 20)?,
+                day_of_month: row.get(offset + 21)?,
+                start_date: get_optional_date(row, offset + 22)?,
+                target_minutes: row.get(offset + 23)?,
+            }),
+            None => None,
+        },
+        last_occurrence_date: get_optional_date(row, offset + 24)?,
+        sort_order: row.get(offset + 25)?,
+    })
+}
+
+// 排序用的等级，数值越大越靠前
+const TASK_PRIORITY_RANK: &str = "CASE priority WHEN 'High' THEN 2 WHEN 'Medium' THEN 1 ELSE 0 END";
+const TASK_STATUS_RANK: &str = "CASE status WHEN 'InProgress' THEN 2 WHEN 'Todo' THEN 1 ELSE 0 END";
+
+fn task_order_by(sort_by: TaskSortBy) -> String {
+    match sort_by {
+        TaskSortBy::Manual => "sort_order, created_at DESC".to_string(),
+        TaskSortBy::CreatedAt => "created_at DESC".to_string(),
+        TaskSortBy::Priority => format!("{} DESC, due_date IS NULL, due_date, created_at DESC", TASK_PRIORITY_RANK),
+        TaskSortBy::DueDate => format!("due_date IS NULL, due_date, {} DESC, created_at DESC", TASK_PRIORITY_RANK),
+        TaskSortBy::Status => format!("{} DESC, created_at DESC", TASK_STATUS_RANK),
+    }
+}
+
+fn map_tag(row: &Row, offset: usize) -> SqliteResult<Tag> {
+    Ok(Tag {
+        id: row.get(offset)?,
+        name: row.get(offset + 1)?,
+        created_at: get_time(row, offset + 2)?,
+        updated_at: get_time(row, offset + 3)?,
+    })
+}
+
+fn map_session(row: &Row, offset: usize) -> SqliteResult<TaskSession> {
+    Ok(TaskSession {
+        id: row.get(offset)?,
+        task_id: row.get(offset + 1)?,
+        start_time: get_time(row, offset + 2)?,
+        end_time: get_optional_time(row, offset + 3)?,
+        duration_minutes: row.get(offset + 4)?,
+        duration_seconds: row.get(offset + 5)?,
+        completed: row.get(offset + 6)?,
+        outcome: get_optional_enum(row, offset + 7, SessionOutcome::parse)?,
+        kind: get_enum(row, offset + 8, SessionKind::parse)?,
+        planned_minutes: row.get(offset + 9)?,
+        is_paused: row.get(offset + 10)?,
+        paused_at: get_optional_time(row, offset + 11)?,
+        total_paused_duration_ms: row.get(offset + 12)?,
+        needs_review: row.get(offset + 13)?,
+        is_manual: row.get(offset + 14)?,
+        created_at: get_time(row, offset + 15)?,
+    })
+}
+
+fn map_subtask(row: &Row, offset: usize) -> SqliteResult<Subtask> {
+    let completed_at = get_optional_time(row, offset + 4)?;
+    Ok(Subtask {
+        id: row.get(offset)?,
+        task_id: row.get(offset + 1)?,
+        title: row.get(offset + 2)?,
+        position: row.get(offset + 3)?,
+        completed: completed_at.is_some(),
+        completed_at,
+        created_at: get_time(row, offset + 5)?,
+        updated_at: get_time(row, offset + 6)?,
+    })
+}
+
+// 关联对象的 id 取自 scope 对应的那一列，该列为空同样视为数据损坏
+fn map_goal(row: &Row, offset: usize) -> SqliteResult<Goal> {
+    let scope = get_enum(row, offset + 2, GoalScope::parse)?;
+    let scope_idx = offset
+        + match scope {
+            GoalScope::Task => 3,
+            GoalScope::TaskGroup => 4,
+            GoalScope::Tag => 5,
+        };
+    let scope_id = row.get::<_, Option<String>>(scope_idx)?.ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(scope_idx, rusqlite::types::Type::Null, "goal has no scope id".into())
+    })?;
+    Ok(Goal {
+        id: row.get(offset)?,
+        name: row.get(offset + 1)?,
+        scope,
+        scope_id,
+        period: get_enum(row, offset + 6, GoalPeriod::parse)?,
+        days_of_week: get_days_of_week(row, offset + 7)?,
+        target_minutes: row.get(offset + 8)?,
+        created_at: get_time(row, offset + 9)?,
+        updated_at: get_time(row, offset + 10)?,
+        timezone: row.get(offset + 11)?,
+    })
+}
+
+fn map_session_detail(row: &Row) -> SqliteResult<SessionDetail> {
+    let task_offset = column_count(SESSION_COLUMNS);
+    let group_offset = task_offset + column_count(TASK_COLUMNS);
+    Ok(SessionDetail {
+        session: map_session(row, 0)?,
+        task: map_task(row, task_offset)?,
+        task_group: map_task_group(row, group_offset)?,
+    })
+}
+
+// 活动会话的查询在会话详情的列之后追加清单的总数和已完成数
+fn map_active_session(row: &Row) -> SqliteResult<ActiveSession> {
+    let detail = map_session_detail(row)?;
+    let checklist_offset = column_count(SESSION_COLUMNS) + column_count(TASK_COLUMNS) + column_count(TASK_GROUP_COLUMNS);
+    Ok(ActiveSession {
+        session: detail.session,
+        task: detail.task,
+        task_group: detail.task_group,
+        checklist: checklist_progress(row.get(checklist_offset)?, row.get(checklist_offset + 1)?),
+    })
+}
+
+fn checklist_progress(total_items: i32, completed_items: i32) -> ChecklistProgress {
+    let progress = if total_items > 0 {
+        completed_items as f64 / total_items as f64
+    } else {
+        0.0
+    };
+    ChecklistProgress {
+        total_items,
+        completed_items,
+        progress,
+    }
+}
+
+// 自动关闭的会话结果未知，不计入完成率的分母
+fn completion_rate(completed_sessions: i32, total_sessions: i32, auto_closed_sessions: i32) -> f64 {
+    let decided_sessions = total_sessions - auto_closed_sessions;
+    if decided_sessions > 0 {
+        completed_sessions as f64 / decided_sessions as f64
+    } else {
+        0.0
+    }
+}
+
+struct FocusSessionRow {
+    task_id: String,
+    task_group_id: String,
+    task_group_name: String,
+    task_group_color: Option<String>,
+    outcome: Option<SessionOutcome>,
+    is_manual: bool,
+    clipped: stats::ClippedSession,
+}
+
+/// Finished focus sessions overlapping `[range_start, range_end)`, clipped to the range.
+fn load_focus_sessions(
+    conn: &Connection,
+    include_manual: bool,
+    range_start: DateTime<Utc>,
+    range_end: DateTime<Utc>,
+) -> AppResult<Vec<FocusSessionRow>> {
+    // SQL 中只按 UTC 文本粗筛（多留一天余量），精确的区间判断在解析时间后进行
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT s.task_id, t.task_group_id, tg.name, s.start_time, s.end_time,
+               COALESCE(s.duration_seconds, 0), s.outcome, s.is_manual, tg.color
+        FROM task_sessions s
+        JOIN tasks t ON s.task_id = t.id
+        JOIN task_groups tg ON t.task_group_id = tg.id
+        WHERE s.end_time IS NOT NULL
+          AND s.kind = 'Focus'
+          AND (? OR s.is_manual = 0)
+          AND s.start_time < ? AND s.end_time > ?
+        ORDER BY s.start_time
+        "#
+    )?;
+    let rows = stmt.query_map(
+        rusqlite::params![
+            include_manual,
+            (range_end + chrono::Duration::days(1)).to_rfc3339(),
+            (range_start - chrono::Duration::days(1)).to_rfc3339(),
+        ],
+        |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                get_time(row, 3)?,
+                get_time(row, 4)?,
+                row.get::<_, i64>(5)?,
+                get_optional_enum(row, 6, SessionOutcome::parse)?,
+                row.get::<_, bool>(7)?,
+                row.get::<_, Option<String>>(8)?,
+            ))
+        },
+    )?;
+
+    let mut sessions = Vec::new();
+    for row in rows {
+        let (task_id, task_group_id, task_group_name, start, end, duration_seconds, outcome, is_manual, task_group_color) = row?;
+        if let Some(clipped) = stats::ClippedSession::clip(start, end, duration_seconds, range_start, range_end) {
+            sessions.push(FocusSessionRow {
+                task_id,
+                task_group_id,
+                task_group_name,
+                task_group_color,
+                outcome,
+                is_manual,
+                clipped,
+            });
+        }
+    }
+    Ok(sessions)
+}
+
+// 新建的任务组和任务排在最前面，与原先按创建时间倒序的显示一致
+fn top_sort_order(conn: &Connection, table: &str, scope: Option<(&str, &str)>) -> SqliteResult<i32> {
+    match scope {
+        Some((column, value)) => conn.query_row(
+            &format!("SELECT COALESCE(MIN(sort_order) - 1, 0) FROM {} WHERE {} = ?", table, column),
+            [value],
+            |row| row.get(0),
+        ),
+        None => conn.query_row(
+            &format!("SELECT COALESCE(MIN(sort_order) - 1, 0) FROM {}", table),
+            [],
+            |row| row.get(0),
+        ),
+    }
+}
+
+// 列出的记录按给定顺序排在前面，其余记录（如被隐藏的已归档项）保持原有相对顺序
+fn apply_sort_order(conn: &Connection, table: &str, scope: Option<(&str, &str)>, ids: &[String]) -> AppResult<()> {
+    let (filter, params): (String, Vec<&str>) = match scope {
+        Some((column, value)) => (format!("AND {} = ?", column), vec![value]),
+        None => (String::new(), vec![]),
+    };
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id FROM {} WHERE deleted_at IS NULL {} ORDER BY sort_order, created_at DESC",
+        table, filter
+    ))?;
+    let current = stmt
+        .query_map(rusqlite::params_from_iter(params), |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+
+    let mut seen = std::collections::HashSet::new();
+    for id in ids {
+        if !current.contains(id) {
+            return Err(AppError::Validation(format!("{} is not part of this ordering", id)));
+        }
+        if !seen.insert(id.as_str()) {
+            return Err(AppError::Validation(format!("{} is listed more than once", id)));
+        }
+    }
+
+    let remaining = current.iter().filter(|id| !seen.contains(id.as_str()));
+    for (position, id) in ids.iter().chain(remaining).enumerate() {
+        conn.execute(
+            &format!("UPDATE {} SET sort_order = ? WHERE id = ?", table),
+            rusqlite::params![position as i32, id],
+        )?;
+    }
+    Ok(())
+}
+
+// 未归档、未删除（所在任务组也是）的重复任务
+fn load_routines(conn: &Connection) -> AppResult<Vec<Task>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM tasks t
+        JOIN task_groups tg ON t.task_group_id = tg.id
+        WHERE t.recurrence_rule IS NOT NULL
+          AND t.archived_at IS NULL AND t.deleted_at IS NULL
+          AND tg.archived_at IS NULL AND tg.deleted_at IS NULL
+        ORDER BY t.created_at
+        "#,
+        prefixed("t", TASK_COLUMNS)
+    ))?;
+    let tasks = stmt
+        .query_map([], |row| map_task(row, 0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(tasks)
+}
+
+fn routine_occurs_on(task: &Task, zone: stats::Zone, date: NaiveDate) -> bool {
+    match &task.recurrence {
+        Some(rule) => {
+            let anchor = rule.start_date.unwrap_or_else(|| zone.local_date(task.created_at));
+            recurrence::occurs_on(rule, anchor, date)
+        }
+        None => false,
+    }
+}
+
+// 按任务汇总截至 range_end 的专注时间（秒）
+fn load_actual_seconds(
+    conn: &Connection,
+    include_manual: bool,
+    range_end: DateTime<Utc>,
+) -> AppResult<HashMap<String, i64>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT task_id, SUM(COALESCE(duration_seconds, 0))
+        FROM task_sessions
+        WHERE end_time IS NOT NULL
+          AND kind = 'Focus'
+          AND (? OR is_manual = 0)
+          AND start_time < ?
+        GROUP BY task_id
+        "#
+    )?;
+    let totals = stmt
+        .query_map(rusqlite::params![include_manual, range_end.to_rfc3339()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?
+        .collect::<SqliteResult<HashMap<_, _>>>()?;
+    Ok(totals)
+}
+
+fn estimate_ratio(actual_seconds: i64, estimate_minutes: i32) -> f64 {
+    if estimate_minutes > 0 {
+        actual_seconds as f64 / (estimate_minutes as f64 * 60.0)
+    } else {
+        0.0
+    }
+}
+
+// 名称去除首尾空白后不能为空，且不超过长度上限
+fn validate_name(field: &str, name: &str) -> AppResult<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::Validation(format!("{} must not be empty", field)));
+    }
+    if name.chars().count() > MAX_NAME_LENGTH {
+        return Err(AppError::Validation(format!("{} must be at most {} characters", field, MAX_NAME_LENGTH)));
+    }
+    Ok(name.to_string())
+}
+
+fn validate_description(description: Option<&str>) -> AppResult<()> {
+    match description {
+        Some(description) if description.chars().count() > MAX_DESCRIPTION_LENGTH => Err(AppError::Validation(
+            format!("description must be at most {} characters", MAX_DESCRIPTION_LENGTH),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// 颜色和图标只做长度检查，格式由前端决定
+fn validate_short_text(field: &str, value: Option<&str>, max_length: usize) -> AppResult<()> {
+    match value {
+        Some(value) if value.trim().is_empty() => Err(AppError::Validation(format!("{} cannot be empty", field))),
+        Some(value) if value.chars().count() > max_length => Err(AppError::Validation(
+            format!("{} must be at most {} characters", field, max_length),
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn validate_minutes(field: &str, minutes: Option<i32>) -> AppResult<()> {
+    match minutes {
+        Some(minutes) if minutes <= 0 => Err(AppError::Validation(format!("{} must be positive", field))),
+        _ => Ok(()),
+    }
+}
+
+fn validate_pomodoro(config: Option<&PomodoroConfig>) -> AppResult<()> {
+    if let Some(config) = config {
+        validate_minutes("pomodoro.work_minutes", Some(config.work_minutes))?;
+        validate_minutes("pomodoro.short_break_minutes", Some(config.short_break_minutes))?;
+        validate_minutes("pomodoro.long_break_minutes", Some(config.long_break_minutes))?;
+        if config.long_break_every <= 0 {
+            return Err(AppError::Validation("pomodoro.long_break_every must be positive".to_string()));
+        }
+    }
+    Ok(())
+}
+
+fn validate_priority_patch(priority: Patch<TaskPriority>) -> AppResult<Patch<String>> {
+    match priority {
+        Patch::Null => Err(AppError::Validation("priority cannot be null".to_string())),
+        patch => Ok(patch.map(|priority| priority.as_str().to_string())),
+    }
+}
+
+fn validate_name_patch(field: &str, name: Patch<String>) -> AppResult<Patch<String>> {
+    match name {
+        Patch::Null => Err(AppError::Validation(format!("{} cannot be null", field))),
+        Patch::Value(name) => Ok(Patch::Value(validate_name(field, &name)?)),
+        Patch::Absent => Ok(Patch::Absent),
+    }
+}
+
+/// `UPDATE ... SET` covering only the fields present in a patch request.
+#[derive(Default)]
+struct PatchUpdate {
+    assignments: Vec<String>,
+    values: Vec<SqlValue>,
+}
+
+impl PatchUpdate {
+    fn set(&mut self, column: &str, value: impl Into<SqlValue>) {
+        self.assignments.push(format!("{} = ?", column));
+        self.values.push(value.into());
+    }
+
+    fn patch<T: Into<SqlValue>>(&mut self, column: &str, patch: Patch<T>) {
+        match patch {
+            Patch::Absent => {}
+            Patch::Null => self.set(column, SqlValue::Null),
+            Patch::Value(value) => self.set(column, value),
+        }
+    }
+
+    // 总是更新 updated_at，返回受影响的行数以判断记录是否存在
+    fn execute(mut self, conn: &Connection, table: &str, id: &str, now: DateTime<Utc>) -> SqliteResult<usize> {
+        self.set("updated_at", now.to_rfc3339());
+        self.values.push(id.to_string().into());
+        conn.execute(
+            &format!("UPDATE {} SET {} WHERE id = ?", table, self.assignments.join(", ")),
+            rusqlite::params_from_iter(self.values),
+        )
+    }
+}
+
+fn find_task_group(conn: &Connection, id: &str) -> AppResult<TaskGroup> {
+    conn.query_row(
+        &format!("SELECT {} FROM task_groups WHERE id = ?", TASK_GROUP_COLUMNS),
+        [id],
+        |row| map_task_group(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("task group {}", id)))
+}
+
+// 回收站中的任务组不能再添加子任务组或任务
+fn find_untrashed_task_group(conn: &Connection, id: &str) -> AppResult<TaskGroup> {
+    let group = find_task_group(conn, id)?;
+    if group.deleted_at.is_some() {
+        return Err(AppError::Conflict(format!("task group {} is in the trash", id)));
+    }
+    Ok(group)
+}
+
+// 父任务组必须存在且不在回收站中；沿父链向上查找，防止把任务组移到自己或其后代之下
+fn validate_parent(conn: &Connection, id: Option<&str>, parent_id: &str) -> AppResult<()> {
+    let parent = find_untrashed_task_group(conn, parent_id)?;
+
+    let Some(id) = id else {
+        return Ok(());
+    };
+    let mut visited = std::collections::HashSet::new();
+    let mut current = Some(parent.id);
+    while let Some(ancestor) = current {
+        if ancestor == id {
+            return Err(AppError::Validation(
+                "a task group cannot be nested under itself or one of its descendants".to_string(),
+            ));
+        }
+        if !visited.insert(ancestor.clone()) {
+            return Err(AppError::Corruption(format!("task group {} is part of a parent cycle", ancestor)));
+        }
+        current = conn
+            .query_row("SELECT parent_id FROM task_groups WHERE id = ?", [&ancestor], |row| row.get(0))
+            .optional()?
+            .flatten();
+    }
+    Ok(())
+}
+
+// 父任务组不可见（如已归档）时，子任务组作为顶层节点显示
+fn build_task_group_tree(groups: Vec<TaskGroup>) -> Vec<TaskGroupNode> {
+    let visible: std::collections::HashSet<String> = groups.iter().map(|group| group.id.clone()).collect();
+    let mut children: HashMap<Option<String>, Vec<TaskGroup>> = HashMap::new();
+    for group in groups {
+        let parent = group.parent_id.clone().filter(|parent_id| visible.contains(parent_id));
+        children.entry(parent).or_default().push(group);
+    }
+
+    fn attach(parent: Option<String>, children: &mut HashMap<Option<String>, Vec<TaskGroup>>) -> Vec<TaskGroupNode> {
+        children
+            .remove(&parent)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|task_group| {
+                let nested = attach(Some(task_group.id.clone()), children);
+                TaskGroupNode { task_group, children: nested }
+            })
+            .collect()
+    }
+    attach(None, &mut children)
+}
+
+// 设置或清除 archived_at / deleted_at；已设置的时间戳保持不变
+fn set_marker(conn: &Connection, table: &str, column: &str, id: &str, marked: bool, now: DateTime<Utc>) -> SqliteResult<usize> {
+    let value = if marked {
+        format!("COALESCE({}, ?1)", column)
+    } else {
+        "NULL".to_string()
+    };
+    conn.execute(
+        &format!("UPDATE {} SET {} = {}, updated_at = ?1 WHERE id = ?2", table, column, value),
+        rusqlite::params![now.to_rfc3339(), id],
+    )
+}
+
+fn ensure_not_running(conn: &Connection, label: &str, id: &str, is_running: impl Fn(&ActiveSession) -> bool) -> AppResult<()> {
+    match find_active_session(conn)? {
+        Some(active) if is_running(&active) => Err(AppError::Conflict(format!(
+            "{} {} has a running session {}",
+            label, id, active.session.id
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn find_tag(conn: &Connection, id: &str) -> AppResult<Tag> {
+    conn.query_row(
+        &format!("SELECT {} FROM tags WHERE id = ?", TAG_COLUMNS),
+        [id],
+        |row| map_tag(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("tag {}", id)))
+}
+
+// 标签名不区分大小写唯一
+fn ensure_tag_name_available(conn: &Connection, name: &str, exclude_id: Option<&str>) -> AppResult<()> {
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT id FROM tags WHERE name = ? COLLATE NOCASE AND id != COALESCE(?, '')",
+            rusqlite::params![name, exclude_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match existing {
+        Some(_) => Err(AppError::Conflict(format!("tag \"{}\" already exists", name))),
+        None => Ok(()),
+    }
+}
+
+fn load_task_tags(conn: &Connection, task_id: &str) -> AppResult<Vec<Tag>> {
+    let mut stmt = conn.prepare(&format!(
+        r#"
+        SELECT {} FROM tags tg
+        JOIN task_tags tt ON tt.tag_id = tg.id
+        WHERE tt.task_id = ?
+        ORDER BY tg.name COLLATE NOCASE
+        "#,
+        prefixed("tg", TAG_COLUMNS)
+    ))?;
+    let tags = stmt
+        .query_map([task_id], |row| map_tag(row, 0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(tags)
+}
+
+fn find_subtask(conn: &Connection, id: &str) -> AppResult<Subtask> {
+    conn.query_row(
+        &format!("SELECT {} FROM subtasks WHERE id = ?", SUBTASK_COLUMNS),
+        [id],
+        |row| map_subtask(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("subtask {}", id)))
+}
+
+fn load_subtasks(conn: &Connection, task_id: &str) -> AppResult<Vec<Subtask>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM subtasks WHERE task_id = ? ORDER BY position, created_at",
+        SUBTASK_COLUMNS
+    ))?;
+    let subtasks = stmt
+        .query_map([task_id], |row| map_subtask(row, 0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(subtasks)
+}
+
+fn find_goal(conn: &Connection, id: &str) -> AppResult<Goal> {
+    conn.query_row(
+        &format!("SELECT {} FROM goals WHERE id = ?", GOAL_COLUMNS),
+        [id],
+        |row| map_goal(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("goal {}", id)))
+}
+
+fn load_goals(conn: &Connection) -> AppResult<Vec<Goal>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM goals ORDER BY created_at", GOAL_COLUMNS))?;
+    let goals = stmt
+        .query_map([], |row| map_goal(row, 0))?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    Ok(goals)
+}
+
+// 目标对象的名称和删除时间（标签没有回收站）
+fn find_goal_scope(conn: &Connection, scope: GoalScope, scope_id: &str) -> AppResult<(String, Option<DateTime<Utc>>)> {
+    match scope {
+        GoalScope::Task => find_task(conn, scope_id).map(|task| (task.name, task.deleted_at)),
+        GoalScope::TaskGroup => find_task_group(conn, scope_id).map(|group| (group.name, group.deleted_at)),
+        GoalScope::Tag => find_tag(conn, scope_id).map(|tag| (tag.name, None)),
+    }
+}
+
+fn validate_goal(period: GoalPeriod, days_of_week: Option<&[Weekday]>, target_minutes: i32) -> AppResult<()> {
+    validate_minutes("target_minutes", Some(target_minutes))?;
+    let period_minutes = match period {
+        GoalPeriod::Day => 24 * 60,
+        GoalPeriod::Week => 7 * 24 * 60,
+    };
+    if target_minutes > period_minutes {
+        return Err(AppError::Validation("target_minutes cannot exceed the length of the period".to_string()));
+    }
+    match days_of_week {
+        Some(_) if period == GoalPeriod::Week => {
+            Err(AppError::Validation("days_of_week only applies to daily goals".to_string()))
+        }
+        Some([]) => Err(AppError::Validation("days_of_week cannot be empty".to_string())),
+        _ => Ok(()),
+    }
+}
+
+// 目标的日和周按目标自己的时区划分，进度查询和达成事件因此一致
+fn goal_zone(goal: &Goal) -> AppResult<stats::Zone> {
+    stats::Zone::resolve(goal.timezone.as_deref())
+        .map_err(|_| AppError::Corruption(format!("goal {} has an unknown timezone", goal.id)))
+}
+
+fn goal_bucket(period: GoalPeriod) -> TimeBucket {
+    match period {
+        GoalPeriod::Day => TimeBucket::Day,
+        GoalPeriod::Week => TimeBucket::Week,
+    }
+}
+
+fn goal_active_on(goal: &Goal, date: NaiveDate) -> bool {
+    match &goal.days_of_week {
+        Some(days) => days.contains(&date.weekday()),
+        None => true,
+    }
+}
+
+// 任务组目标包含所有子任务组中的任务；UNION 去重，即使数据中有环也会结束
+fn load_goal_task_ids(conn: &Connection, goal: &Goal) -> AppResult<HashSet<String>> {
+    let sql = match goal.scope {
+        GoalScope::Task => return Ok(HashSet::from([goal.scope_id.clone()])),
+        GoalScope::TaskGroup => {
+            r#"
+            WITH RECURSIVE subtree (id) AS (
+                SELECT ?
+                UNION
+                SELECT tg.id FROM task_groups tg JOIN subtree s ON tg.parent_id = s.id
+            )
+            SELECT id FROM tasks WHERE task_group_id IN subtree
+            "#
+        }
+        GoalScope::Tag => "SELECT task_id FROM task_tags WHERE tag_id = ?",
+    };
+    let mut stmt = conn.prepare(sql)?;
+    let task_ids = stmt
+        .query_map([&goal.scope_id], |row| row.get::<_, String>(0))?
+        .collect::<SqliteResult<HashSet<_>>>()?;
+    Ok(task_ids)
+}
+
+// 按目标周期汇总专注秒数，跨午夜的会话按比例拆分
+fn goal_period_totals(
+    sessions: &[FocusSessionRow],
+    task_ids: &HashSet<String>,
+    zone: stats::Zone,
+    period: GoalPeriod,
+) -> HashMap<NaiveDate, i64> {
+    let mut totals = HashMap::new();
+    for session in sessions.iter().filter(|session| task_ids.contains(&session.task_id)) {
+        for (date, seconds) in session.clipped.seconds_by_day(zone) {
+            *totals.entry(stats::bucket_start(date, goal_bucket(period))).or_default() += seconds;
+        }
+    }
+    totals
+}
+
+fn goal_period_progress(goal: &Goal, period_start: NaiveDate, focused_seconds: i64) -> GoalPeriodProgress {
+    let target_seconds = goal.target_minutes as i64 * 60;
+    GoalPeriodProgress {
+        period_start,
+        focused_minutes: (focused_seconds / 60) as i32,
+        focused_seconds,
+        target_minutes: goal.target_minutes,
+        progress: focused_seconds as f64 / target_seconds as f64,
+        met: focused_seconds >= target_seconds,
+    }
+}
+
+fn find_task(conn: &Connection, id: &str) -> AppResult<Task> {
+    conn.query_row(
+        &format!("SELECT {} FROM tasks WHERE id = ?", TASK_COLUMNS),
+        [id],
+        |row| map_task(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("task {}", id)))
+}
+
+// 任务本身或其所在的任务组在回收站中时，都不能再添加会话
+fn find_untrashed_task(conn: &Connection, id: &str) -> AppResult<Task> {
+    let task = find_task(conn, id)?;
+    let group = find_task_group(conn, &task.task_group_id)?;
+    if task.deleted_at.is_some() || group.deleted_at.is_some() {
+        return Err(AppError::Conflict(format!("task {} is in the trash", id)));
+    }
+    Ok(task)
+}
+
+fn planned_minutes(task: &Task, kind: SessionKind) -> AppResult<Option<i32>> {
+    match (kind, &task.pomodoro) {
+        (SessionKind::Focus, Some(config)) => Ok(Some(config.work_minutes)),
+        (SessionKind::Focus, None) => Ok(task.duration_minutes),
+        (SessionKind::ShortBreak, Some(config)) => Ok(Some(config.short_break_minutes)),
+        (SessionKind::LongBreak, Some(config)) => Ok(Some(config.long_break_minutes)),
+        (_, None) => Err(AppError::Conflict(format!("task {} has no Pomodoro settings", task.id))),
+    }
+}
+
+fn load_pomodoro_state(conn: &Connection, task: &Task, config: &PomodoroConfig) -> AppResult<PomodoroState> {
+    let (completed_focus_sessions, next_kind) = conn
+        .query_row(
+            "SELECT completed_focus_sessions, next_kind FROM pomodoro_state WHERE task_id = ?",
+            [&task.id],
+            |row| Ok((row.get(0)?, get_enum(row, 1, SessionKind::parse)?)),
+        )
+        .optional()?
+        .unwrap_or((0, SessionKind::Focus));
+
+    Ok(PomodoroState {
+        task_id: task.id.clone(),
+        completed_focus_sessions,
+        long_break_every: config.long_break_every,
+        next_kind,
+        next_minutes: planned_minutes(task, next_kind)?.unwrap_or(config.work_minutes),
+    })
+}
+
+// 一个阶段结束后推进番茄钟循环：完成专注后进入休息，每 N 次专注后进入长休息
+fn advance_pomodoro(conn: &Connection, task: &Task, config: &PomodoroConfig, session: &TaskSession, now: DateTime<Utc>) -> AppResult<()> {
+    let state = load_pomodoro_state(conn, task, config)?;
+    let (completed_focus_sessions, next_kind) = match (session.kind, session.outcome) {
+        (SessionKind::Focus, Some(SessionOutcome::Completed)) => {
+            let completed = state.completed_focus_sessions + 1;
+            if completed % config.long_break_every.max(1) == 0 {
+                (completed, SessionKind::LongBreak)
+            } else {
+                (completed, SessionKind::ShortBreak)
+            }
+        }
+        (SessionKind::Focus, _) => (state.completed_focus_sessions, SessionKind::Focus),
+        (SessionKind::ShortBreak, _) => (state.completed_focus_sessions, SessionKind::Focus),
+        (SessionKind::LongBreak, _) => (0, SessionKind::Focus),
+    };
+
+    conn.execute(
+        r#"
+        INSERT INTO pomodoro_state (task_id, completed_focus_sessions, next_kind, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(task_id) DO UPDATE SET
+            completed_focus_sessions = excluded.completed_focus_sessions,
+            next_kind = excluded.next_kind,
+            updated_at = excluded.updated_at
+        "#,
+        rusqlite::params![task.id, completed_focus_sessions, next_kind.as_str(), now.to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+fn find_active_session(conn: &Connection) -> SqliteResult<Option<ActiveSession>> {
+    conn.query_row(
+        &format!(
+            r#"
+            SELECT {}, {}, {},
+                   (SELECT COUNT(*) FROM subtasks st WHERE st.task_id = t.id),
+                   (SELECT COUNT(*) FROM subtasks st WHERE st.task_id = t.id AND st.completed_at IS NOT NULL)
+            FROM task_sessions s
+            JOIN tasks t ON s.task_id = t.id
+            JOIN task_groups tg ON t.task_group_id = tg.id
+            WHERE s.end_time IS NULL
+            ORDER BY s.start_time DESC
+            LIMIT 1
+            "#,
+            prefixed("s", SESSION_COLUMNS),
+            prefixed("t", TASK_COLUMNS),
+            prefixed("tg", TASK_GROUP_COLUMNS),
+        ),
+        [],
+        map_active_session,
+    )
+    .optional()
+}
+
+fn close_session(
+    conn: &Connection,
+    session_id: &str,
+    duration_override: Option<i32>,
+    outcome: Option<SessionOutcome>,
+    now: DateTime<Utc>,
+) -> AppResult<TaskSession> {
+    if let Some(minutes) = duration_override {
+        if minutes < 0 {
+            return Err(AppError::Validation("duration_minutes must not be negative".to_string()));
+        }
+    }
+    let mut session = find_session(conn, session_id)?;
+    if session.end_time.is_some() {
+        return Err(AppError::Conflict(format!("session {} has already ended", session.id)));
+    }
+
+    // 结束时仍处于暂停状态，把这段暂停计入总暂停时长
+    if let (true, Some(paused_at)) = (session.is_paused, session.paused_at) {
+        session.total_paused_duration_ms += (now - paused_at).num_milliseconds().max(0);
+        session.is_paused = false;
+        session.paused_at = None;
+    }
+    session.end_time = Some(now);
+
+    // 客户端传入的时长仅作为覆盖值，默认以服务端计算为准；覆盖值不能超过实际专注的时间
+    let focused_seconds = session.focused_ms(now) / 1000;
+    let duration_seconds = match duration_override {
+        Some(minutes) if minutes as i64 * 60 > focused_seconds => {
+            return Err(AppError::Validation("duration_minutes cannot exceed the focused time".to_string()));
+        }
+        Some(minutes) => minutes as i64 * 60,
+        None => focused_seconds,
+    };
+    let duration_minutes = duration_override.unwrap_or((duration_seconds / 60) as i32);
+
+    // 未指定结果时：倒计时任务未达到目标时长视为放弃，正向计时视为完成
+    let outcome = outcome.unwrap_or(match session.planned_minutes {
+        Some(minutes) if duration_seconds < minutes as i64 * 60 => SessionOutcome::Abandoned,
+        _ => SessionOutcome::Completed,
+    });
+
+    conn.execute(
+        r#"
+        UPDATE task_sessions 
+        SET end_time = ?,
+            duration_minutes = ?,
+            duration_seconds = ?,
+            completed = ?,
+            outcome = ?,
+            is_paused = 0,
+            paused_at = NULL,
+            total_paused_duration_ms = ?
+        WHERE id = ?
+        "#,
+        rusqlite::params![
+            now.to_rfc3339(),
+            duration_minutes,
+            duration_seconds,
+            outcome == SessionOutcome::Completed,
+            outcome.as_str(),
+            session.total_paused_duration_ms,
+            session_id
+        ],
+    )?;
+
+    let session = find_session(conn, session_id)?;
+    let task = find_task(conn, &session.task_id)?;
+    if let Some(config) = &task.pomodoro {
+        advance_pomodoro(conn, &task, config, &session, now)?;
+    }
+
+    Ok(session)
+}
+
+fn validate_session_times(start_time: DateTime<Utc>, end_time: DateTime<Utc>, duration_seconds: i64, now: DateTime<Utc>) -> AppResult<()> {
+    if end_time <= start_time {
+        return Err(AppError::Validation("end_time must be after start_time".to_string()));
+    }
+    if end_time > now {
+        return Err(AppError::Validation("end_time cannot be in the future".to_string()));
+    }
+    if duration_seconds < 0 || duration_seconds > (end_time - start_time).num_seconds() {
+        return Err(AppError::Validation("duration must fit between start_time and end_time".to_string()));
+    }
+    Ok(())
+}
+
+// 会话之间不能重叠，进行中的会话视为持续到现在
+fn ensure_no_overlap(conn: &Connection, exclude_id: Option<&str>, start_time: DateTime<Utc>, end_time: DateTime<Utc>, now: DateTime<Utc>) -> AppResult<()> {
+    let overlapping: Option<String> = conn
+        .query_row(
+            r#"
+            SELECT id FROM task_sessions
+            WHERE id != COALESCE(?, '')
+              AND start_time < ?
+              AND COALESCE(end_time, ?) > ?
+            LIMIT 1
+            "#,
+            rusqlite::params![exclude_id, end_time.to_rfc3339(), now.to_rfc3339(), start_time.to_rfc3339()],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match overlapping {
+        Some(id) => Err(AppError::Conflict(format!("session overlaps with existing session {}", id))),
+        None => Ok(()),
+    }
+}
+
+fn find_session(conn: &Connection, id: &str) -> AppResult<TaskSession> {
+    conn.query_row(
+        &format!("SELECT {} FROM task_sessions WHERE id = ?", SESSION_COLUMNS),
+        [id],
+        |row| map_session(row, 0),
+    )
+    .optional()?
+    .ok_or_else(|| AppError::NotFound(format!("session {}", id)))
+}
 
 pub struct Database {
     conn: Mutex<Connection>,
 }
 
 impl Database {
-    pub fn new(database_path: &str) -> SqliteResult<Self> {
-        let conn = Connection::open(database_path)?;
+    pub fn new(database_path: &str) -> AppResult<Self> {
+        let mut conn = Connection::open(database_path)?;
+        // SQLite 默认不检查外键，ON DELETE CASCADE 也依赖此设置
+        conn.pragma_update(None, "foreign_keys", true)?;
+        migrations::run(&mut conn)?;
+        println!("Database schema at version {}", migrations::schema_version(&conn)?);
+
         let db = Database {
             conn: Mutex::new(conn),
         };
-        db.init()?;
+        db.recover_sessions()?;
         Ok(db)
     }
 
-    fn init(&self) -> SqliteResult<()> {
+    // 启动时处理崩溃或强制退出遗留的未结束会话（唯一索引保证最多只有一个）：
+    // 已超过上限时长的按上限自动关闭并标记为待确认，否则继续计时
+    fn recover_sessions(&self) -> SqliteResult<()> {
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        let session = conn
+            .query_row(
+                &format!("SELECT {} FROM task_sessions WHERE end_time IS NULL", SESSION_COLUMNS),
+                [],
+                |row| map_session(row, 0),
+            )
+            .optional()?;
+        let Some(session) = session else {
+            return Ok(());
+        };
+
+        let cap_ms = session.planned_minutes.map(|m| m as i64).unwrap_or(RECOVERED_SESSION_CAP_MINUTES) * 60_000;
+        let focused_ms = session.focused_ms(now);
+        if focused_ms < cap_ms {
+            return Ok(());
+        }
+
+        let end_time = session.start_time + chrono::Duration::milliseconds(session.total_paused_duration_ms + cap_ms);
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET end_time = ?,
+                duration_minutes = ?,
+                duration_seconds = ?,
+                completed = 0,
+                outcome = ?,
+                is_paused = 0,
+                paused_at = NULL,
+                needs_review = 1
+            WHERE id = ?
+            "#,
+            rusqlite::params![
+                end_time.to_rfc3339(),
+                cap_ms / 60_000,
+                cap_ms / 1000,
+                SessionOutcome::AutoClosed.as_str(),
+                session.id
+            ],
+        )?;
+
+        println!("Recovered orphaned session {} ({} s)", session.id, cap_ms / 1000);
+        Ok(())
+    }
+
+    // Task Group operations
+    pub fn create_task_group(&self, req: CreateTaskGroupRequest) -> AppResult<TaskGroup> {
+        let name = validate_name("name", &req.name)?;
+        validate_description(req.description.as_deref())?;
+        validate_short_text("color", req.color.as_deref(), MAX_COLOR_LENGTH)?;
+        validate_short_text("icon", req.icon.as_deref(), MAX_ICON_LENGTH)?;
+        validate_minutes("default_duration_minutes", req.default_duration_minutes)?;
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        
+        let conn = self.conn.lock().unwrap();
+        if let Some(parent_id) = &req.parent_id {
+            validate_parent(&conn, None, parent_id)?;
+        }
+        let sort_order = top_sort_order(&conn, "task_groups", None)?;
+        conn.execute(
+            r#"
+            INSERT INTO task_groups (id, name, description, created_at, updated_at, sort_order, parent_id,
+                color, icon, default_duration_minutes, default_timer_mode)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            rusqlite::params![
+                id,
+                name,
+                req.description.as_deref().unwrap_or(""),
+                now.to_rfc3339(),
+                now.to_rfc3339(),
+                sort_order,
+                req.parent_id,
+                req.color,
+                req.icon,
+                req.default_duration_minutes,
+                req.default_timer_mode.map(|mode| mode.as_str())
+            ],
+        )?;
+
+        Ok(TaskGroup {
+            id,
+            name,
+            description: req.description,
+            created_at: now,
+            updated_at: now,
+            archived_at: None,
+            deleted_at: None,
+            sort_order,
+            parent_id: req.parent_id,
+            color: req.color,
+            icon: req.icon,
+            default_duration_minutes: req.default_duration_minutes,
+            default_timer_mode: req.default_timer_mode,
+        })
+    }
+
+    pub fn get_task_group_tree(&self, include_archived: bool) -> AppResult<Vec<TaskGroupNode>> {
+        Ok(build_task_group_tree(self.get_task_groups(include_archived)?))
+    }
+
+    pub fn get_task_groups(&self, include_archived: bool) -> AppResult<Vec<TaskGroup>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!(
+                r#"
+                SELECT {} FROM task_groups
+                WHERE deleted_at IS NULL
+                  AND (? OR archived_at IS NULL)
+                ORDER BY sort_order, created_at DESC
+                "#,
+                TASK_GROUP_COLUMNS
+            )
+        )?;
+        
+        let group_iter = stmt.query_map([include_archived], |row| map_task_group(row, 0))?;
+
+        let mut groups = Vec::new();
+        for group in group_iter {
+            groups.push(group?);
+        }
+        Ok(groups)
+    }
+
+    pub fn update_task_group(&self, id: &str, req: UpdateTaskGroupRequest) -> AppResult<TaskGroup> {
+        let name = validate_name_patch("name", req.name)?;
+        validate_description(req.description.value().map(String::as_str))?;
+        validate_short_text("color", req.color.value().map(String::as_str), MAX_COLOR_LENGTH)?;
+        validate_short_text("icon", req.icon.value().map(String::as_str), MAX_ICON_LENGTH)?;
+        validate_minutes("default_duration_minutes", req.default_duration_minutes.value().copied())?;
+        let now = Utc::now();
+
+        let mut update = PatchUpdate::default();
+        update.patch("name", name);
+        update.patch("description", req.description);
+        update.patch("color", req.color);
+        update.patch("icon", req.icon);
+        update.patch("default_duration_minutes", req.default_duration_minutes);
+        update.patch("default_timer_mode", req.default_timer_mode.map(|mode| mode.as_str().to_string()));
+
+        let conn = self.conn.lock().unwrap();
+        if let Some(parent_id) = req.parent_id.value() {
+            find_task_group(&conn, id)?;
+            validate_parent(&conn, Some(id), parent_id)?;
+        }
+        update.patch("parent_id", req.parent_id);
+        let updated = update.execute(&conn, "task_groups", id, now)?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("task group {}", id)));
+        }
+
+        find_task_group(&conn, id)
+    }
+
+    pub fn reorder_task_groups(&self, req: ReorderTaskGroupsRequest) -> AppResult<Vec<TaskGroup>> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            apply_sort_order(&tx, "task_groups", None, &req.task_group_ids)?;
+            tx.commit()?;
+        }
+        self.get_task_groups(false)
+    }
+
+    pub fn archive_task_group(&self, id: &str) -> AppResult<TaskGroup> {
+        self.mark_task_group(id, "archived_at", true)
+    }
+
+    pub fn unarchive_task_group(&self, id: &str) -> AppResult<TaskGroup> {
+        self.mark_task_group(id, "archived_at", false)
+    }
+
+    // 移入回收站只做标记，组内任务和会话历史都保留
+    pub fn trash_task_group(&self, id: &str) -> AppResult<TaskGroup> {
+        {
+            let conn = self.conn.lock().unwrap();
+            ensure_not_running(&conn, "task group", id, |active| active.task_group.id == id)?;
+        }
+        self.mark_task_group(id, "deleted_at", true)
+    }
+
+    pub fn restore_task_group(&self, id: &str) -> AppResult<TaskGroup> {
+        self.mark_task_group(id, "deleted_at", false)
+    }
+
+    // 永久删除会级联删除组内任务及其会话，只允许删除回收站中的组
+    pub fn purge_task_group(&self, id: &str) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let group = find_task_group(&conn, id)?;
+        if group.deleted_at.is_none() {
+            return Err(AppError::Conflict(format!("task group {} must be moved to the trash before purging", id)));
+        }
+        ensure_not_running(&conn, "task group", id, |active| active.task_group.id == id)?;
+        conn.execute("DELETE FROM task_groups WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    fn mark_task_group(&self, id: &str, column: &str, marked: bool) -> AppResult<TaskGroup> {
+        let conn = self.conn.lock().unwrap();
+        if set_marker(&conn, "task_groups", column, id, marked, Utc::now())? == 0 {
+            return Err(AppError::NotFound(format!("task group {}", id)));
+        }
+        find_task_group(&conn, id)
+    }
+
+    // Task operations
+    pub fn create_task(&self, req: CreateTaskRequest) -> AppResult<Task> {
+        let name = validate_name("name", &req.name)?;
+        validate_description(req.description.as_deref())?;
+        validate_minutes("duration_minutes", req.duration_minutes)?;
+        validate_minutes("estimate_minutes", req.estimate_minutes)?;
+        validate_pomodoro(req.pomodoro.as_ref())?;
+        if let Some(recurrence) = &req.recurrence {
+            recurrence::validate(recurrence)?;
+        }
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        
+        let priority = req.priority.unwrap_or(TaskPriority::Medium);
+
+        let conn = self.conn.lock().unwrap();
+        let group = find_untrashed_task_group(&conn, &req.task_group_id)?;
+        // 未指定时长时继承任务组的默认计时设置；番茄钟任务的时长由番茄钟配置决定。
+        // 计时方式与时长互相矛盾时报错，而不是悄悄选其中一个
+        if req.timer_mode == Some(TimerMode::Forward) && req.duration_minutes.is_some() {
+            return Err(AppError::Validation("forward tasks cannot have duration_minutes".to_string()));
+        }
+        let mode = req.timer_mode.or(group.default_timer_mode);
+        let duration_minutes = match (req.duration_minutes, mode) {
+            (Some(minutes), _) => Some(minutes),
+            (None, _) if req.pomodoro.is_some() => None,
+            (None, Some(TimerMode::Forward)) => None,
+            (None, Some(TimerMode::Countdown)) => match group.default_duration_minutes {
+                Some(minutes) => Some(minutes),
+                None => {
+                    return Err(AppError::Validation(
+                        "countdown tasks need duration_minutes when the group has no default".to_string(),
+                    ));
+                }
+            },
+            (None, None) => group.default_duration_minutes,
+        };
+        let sort_order = top_sort_order(&conn, "tasks", Some(("task_group_id", &req.task_group_id)))?;
+        conn.execute(
+            r#"
+            INSERT INTO tasks (id, task_group_id, name, description, duration_minutes,
+                pomodoro_work_minutes, pomodoro_short_break_minutes, pomodoro_long_break_minutes, pomodoro_long_break_every,
+                created_at, updated_at, status, priority, due_date, estimate_minutes,
+                recurrence_rule, recurrence_days, recurrence_interval_days, recurrence_day_of_month,
+                recurrence_start_date, recurrence_target_minutes, sort_order)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            rusqlite::params![
+                id,
+                req.task_group_id,
+                name,
+                req.description,
+                duration_minutes,
+                req.pomodoro.as_ref().map(|p| p.work_minutes),
+                req.pomodoro.as_ref().map(|p| p.short_break_minutes),
+                req.pomodoro.as_ref().map(|p| p.long_break_minutes),
+                req.pomodoro.as_ref().map(|p| p.long_break_every),
+                now.to_rfc3339(),
+                now.to_rfc3339(),
+                TaskStatus::Todo.as_str(),
+                priority.as_str(),
+                req.due_date.map(|date| date.to_string()),
+                req.estimate_minutes,
+                req.recurrence.as_ref().map(|r| r.rule.as_str()),
+                req.recurrence.as_ref().and_then(|r| r.days_of_week.as_deref().map(recurrence::days_to_mask)),
+                req.recurrence.as_ref().and_then(|r| r.interval_days),
+                req.recurrence.as_ref().and_then(|r| r.day_of_month),
+                req.recurrence.as_ref().and_then(|r| r.start_date.map(|date| date.to_string())),
+                req.recurrence.as_ref().and_then(|r| r.target_minutes),
+                sort_order
+            ],
+        )?;
+
+        Ok(Task {
+            id,
+            task_group_id: req.task_group_id,
+            name,
+            description: req.description,
+            duration_minutes,
+            pomodoro: req.pomodoro,
+            created_at: now,
+            updated_at: now,
+            archived_at: None,
+            deleted_at: None,
+            status: TaskStatus::Todo,
+            priority,
+            due_date: req.due_date,
+            completed_at: None,
+            estimate_minutes: req.estimate_minutes,
+            recurrence: req.recurrence,
+            last_occurrence_date: None,
+            sort_order,
+        })
+    }
+
+    pub fn update_task(&self, id: &str, req: UpdateTaskRequest) -> AppResult<Task> {
+        if let Patch::Null = req.task_group_id {
+            return Err(AppError::Validation("task_group_id cannot be null".to_string()));
+        }
+        let name = validate_name_patch("name", req.name)?;
+        validate_description(req.description.value().map(String::as_str))?;
+        validate_minutes("duration_minutes", req.duration_minutes.value().copied())?;
+        validate_minutes("estimate_minutes", req.estimate_minutes.value().copied())?;
+        validate_pomodoro(req.pomodoro.value())?;
+        if let Some(recurrence) = req.recurrence.value() {
+            recurrence::validate(recurrence)?;
+        }
+        let priority = validate_priority_patch(req.priority)?;
+        let now = Utc::now();
+
+        let mut update = PatchUpdate::default();
+        update.patch("name", name);
+        update.patch("description", req.description);
+        update.patch("duration_minutes", req.duration_minutes);
+        // 番茄钟的四个字段作为整体设置或清除
+        let pomodoro = req.pomodoro;
+        update.patch("pomodoro_work_minutes", pomodoro.clone().map(|p| p.work_minutes));
+        update.patch("pomodoro_short_break_minutes", pomodoro.clone().map(|p| p.short_break_minutes));
+        update.patch("pomodoro_long_break_minutes", pomodoro.clone().map(|p| p.long_break_minutes));
+        update.patch("pomodoro_long_break_every", pomodoro.map(|p| p.long_break_every));
+        update.patch("priority", priority);
+        update.patch("due_date", req.due_date.map(|date| date.to_string()));
+        update.patch("estimate_minutes", req.estimate_minutes);
+        // 重复规则同样整体设置或清除
+        let rule = req.recurrence;
+        update.patch("recurrence_rule", rule.clone().map(|r| r.rule.as_str().to_string()));
+        update.patch("recurrence_days", rule.clone().map(|r| r.days_of_week.as_deref().map(recurrence::days_to_mask)));
+        update.patch("recurrence_interval_days", rule.clone().map(|r| r.interval_days));
+        update.patch("recurrence_day_of_month", rule.clone().map(|r| r.day_of_month));
+        update.patch("recurrence_start_date", rule.clone().map(|r| r.start_date.map(|date| date.to_string())));
+        update.patch("recurrence_target_minutes", rule.map(|r| r.target_minutes));
+
+        let conn = self.conn.lock().unwrap();
+        // 移动到其他任务组时排在该组最前面，会话通过 task_id 跟随任务
+        if let Some(task_group_id) = req.task_group_id.value() {
+            let group = find_untrashed_task_group(&conn, task_group_id)?;
+            let task = find_task(&conn, id)?;
+            if task.task_group_id != group.id {
+                update.set("task_group_id", group.id.clone());
+                update.set("sort_order", top_sort_order(&conn, "tasks", Some(("task_group_id", &group.id)))?);
+            }
+        }
+        let updated = update.execute(&conn, "tasks", id, now)?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("task {}", id)));
+        }
+
+        find_task(&conn, id)
+    }
+
+    pub fn reorder_tasks(&self, req: ReorderTasksRequest) -> AppResult<Vec<Task>> {
+        {
+            let mut conn = self.conn.lock().unwrap();
+            let tx = conn.transaction()?;
+            find_task_group(&tx, &req.task_group_id)?;
+            apply_sort_order(&tx, "tasks", Some(("task_group_id", &req.task_group_id)), &req.task_ids)?;
+            tx.commit()?;
+        }
+        self.get_tasks_by_group(&req.task_group_id, TaskFilter::default())
+    }
+
+    // 进入 Done 时记录完成时间，离开 Done 时清除
+    pub fn set_task_status(&self, req: SetTaskStatusRequest) -> AppResult<Task> {
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            r#"
+            UPDATE tasks
+            SET completed_at = CASE
+                    WHEN ?1 != 'Done' THEN NULL
+                    WHEN status = 'Done' THEN completed_at
+                    ELSE ?2
+                END,
+                status = ?1,
+                updated_at = ?2
+            WHERE id = ?3
+            "#,
+            rusqlite::params![req.status.as_str(), now, req.task_id],
+        )?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("task {}", req.task_id)));
+        }
+
+        println!("Task {} moved to {}", req.task_id, req.status.as_str());
+        find_task(&conn, &req.task_id)
+    }
+
+    // 跨任务组查询今天到期和已逾期的未完成任务
+    pub fn get_due_tasks(&self, req: DueTasksRequest) -> AppResult<DueTasks> {
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        let today = zone.local_date(Utc::now());
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {} FROM tasks t
+            JOIN task_groups tg ON t.task_group_id = tg.id
+            WHERE t.due_date IS NOT NULL
+              AND t.due_date <= ?
+              AND t.status != 'Done'
+              AND t.archived_at IS NULL AND t.deleted_at IS NULL
+              AND tg.archived_at IS NULL AND tg.deleted_at IS NULL
+            ORDER BY t.due_date, {} DESC, t.created_at
+            "#,
+            prefixed("t", TASK_COLUMNS),
+            TASK_PRIORITY_RANK.replace("priority", "t.priority")
+        ))?;
+        let tasks = stmt
+            .query_map([today.to_string()], |row| map_task(row, 0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let (due_today, overdue) = tasks
+            .into_iter()
+            .partition(|task| task.due_date == Some(today));
+        Ok(DueTasks {
+            date: today,
+            overdue,
+            due_today,
+        })
+    }
+
+    // 对今天有发生的重复任务重置为当天的实例，每天只处理一次
+    pub fn run_routines(&self, zone: stats::Zone, today: NaiveDate) -> AppResult<Vec<Task>> {
+        let now = Utc::now().to_rfc3339();
+        let conn = self.conn.lock().unwrap();
+        let mut reset = Vec::new();
+        for task in load_routines(&conn)? {
+            if task.last_occurrence_date.is_some_and(|date| date >= today) || !routine_occurs_on(&task, zone, today) {
+                continue;
+            }
+            conn.execute(
+                r#"
+                UPDATE tasks
+                SET status = 'Todo', completed_at = NULL, due_date = ?1, last_occurrence_date = ?1, updated_at = ?2
+                WHERE id = ?3
+                "#,
+                rusqlite::params![today.to_string(), now, task.id],
+            )?;
+            println!("Routine reset for {}: {}", today, task.name);
+            reset.push(find_task(&conn, &task.id)?);
+        }
+        Ok(reset)
+    }
+
+    pub fn get_due_routines(&self, req: DueTasksRequest) -> AppResult<DueRoutines> {
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        let today = zone.local_date(Utc::now());
+        let day_start = zone.start_of_day(today);
+        let day_end = zone.start_of_day(today + chrono::Duration::days(1));
+
+        let conn = self.conn.lock().unwrap();
+        let mut focused: HashMap<String, i64> = HashMap::new();
+        for session in load_focus_sessions(&conn, true, day_start, day_end)? {
+            *focused.entry(session.task_id).or_default() += session.clipped.duration_seconds;
+        }
+
+        let mut routines = Vec::new();
+        for task in load_routines(&conn)? {
+            if !routine_occurs_on(&task, zone, today) {
+                continue;
+            }
+            let task_group_name = find_task_group(&conn, &task.task_group_id)?.name;
+            let target_minutes = task
+                .recurrence
+                .as_ref()
+                .and_then(|r| r.target_minutes)
+                .or(task.duration_minutes);
+            let focused_seconds = focused.get(&task.id).copied().unwrap_or(0);
+            let target_met = match target_minutes {
+                Some(minutes) => focused_seconds >= minutes as i64 * 60,
+                None => focused_seconds > 0,
+            };
+            routines.push(RoutineProgress {
+                task,
+                task_group_name,
+                target_minutes,
+                focused_minutes: (focused_seconds / 60) as i32,
+                focused_seconds,
+                target_met,
+            });
+        }
+
+        Ok(DueRoutines { date: today, routines })
+    }
+
+    pub fn archive_task(&self, id: &str) -> AppResult<Task> {
+        self.mark_task(id, "archived_at", true)
+    }
+
+    pub fn unarchive_task(&self, id: &str) -> AppResult<Task> {
+        self.mark_task(id, "archived_at", false)
+    }
+
+    pub fn trash_task(&self, id: &str) -> AppResult<Task> {
+        {
+            let conn = self.conn.lock().unwrap();
+            ensure_not_running(&conn, "task", id, |active| active.task.id == id)?;
+        }
+        self.mark_task(id, "deleted_at", true)
+    }
+
+    pub fn restore_task(&self, id: &str) -> AppResult<Task> {
+        self.mark_task(id, "deleted_at", false)
+    }
+
+    pub fn purge_task(&self, id: &str) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let task = find_task(&conn, id)?;
+        if task.deleted_at.is_none() {
+            return Err(AppError::Conflict(format!("task {} must be moved to the trash before purging", id)));
+        }
+        ensure_not_running(&conn, "task", id, |active| active.task.id == id)?;
+        conn.execute("DELETE FROM tasks WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    fn mark_task(&self, id: &str, column: &str, marked: bool) -> AppResult<Task> {
+        let conn = self.conn.lock().unwrap();
+        if set_marker(&conn, "tasks", column, id, marked, Utc::now())? == 0 {
+            return Err(AppError::NotFound(format!("task {}", id)));
+        }
+        find_task(&conn, id)
+    }
+
+    pub fn get_trash(&self) -> AppResult<Trash> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM task_groups WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+            TASK_GROUP_COLUMNS
+        ))?;
+        let task_groups = stmt
+            .query_map([], |row| map_task_group(row, 0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM tasks WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+            TASK_COLUMNS
+        ))?;
+        let tasks = stmt
+            .query_map([], |row| map_task(row, 0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        Ok(Trash { task_groups, tasks })
+    }
+
+    // Tag operations
+    pub fn create_tag(&self, req: CreateTagRequest) -> AppResult<Tag> {
+        let name = validate_name("name", &req.name)?;
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        ensure_tag_name_available(&conn, &name, None)?;
+        conn.execute(
+            "INSERT INTO tags (id, name, created_at, updated_at) VALUES (?, ?, ?, ?)",
+            rusqlite::params![id, name, now.to_rfc3339(), now.to_rfc3339()],
+        )?;
+
+        Ok(Tag {
+            id,
+            name,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn get_tags(&self) -> AppResult<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            &format!("SELECT {} FROM tags ORDER BY name COLLATE NOCASE", TAG_COLUMNS)
+        )?;
+        let tags = stmt
+            .query_map([], |row| map_tag(row, 0))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+        Ok(tags)
+    }
+
+    pub fn update_tag(&self, id: &str, req: UpdateTagRequest) -> AppResult<Tag> {
+        let name = validate_name("name", &req.name)?;
+        let now = Utc::now();
+
         let conn = self.conn.lock().unwrap();
-        
-        // Create task_groups table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS task_groups (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL,
-                description TEXT,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
-            )
-            "#,
-            [],
+        ensure_tag_name_available(&conn, &name, Some(id))?;
+        let updated = conn.execute(
+            "UPDATE tags SET name = ?, updated_at = ? WHERE id = ?",
+            rusqlite::params![name, now.to_rfc3339(), id],
         )?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("tag {}", id)));
+        }
+        find_tag(&conn, id)
+    }
 
-        // Create tasks table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS tasks (
-                id TEXT PRIMARY KEY,
-                task_group_id TEXT NOT NULL,
-                name TEXT NOT NULL,
-                description TEXT,
-                duration_minutes INTEGER,
-                created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL,
-                FOREIGN KEY (task_group_id) REFERENCES task_groups (id) ON DELETE CASCADE
-            )
-            "#,
-            [],
-        )?;
+    // 删除标签只会移除任务上的关联，任务和会话保持不变
+    pub fn delete_tag(&self, id: &str) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn.execute("DELETE FROM tags WHERE id = ?", [id])?;
+        if deleted == 0 {
+            return Err(AppError::NotFound(format!("tag {}", id)));
+        }
+        Ok(())
+    }
 
-        // Create task_sessions table
-        conn.execute(
-            r#"
-            CREATE TABLE IF NOT EXISTS task_sessions (
-                id TEXT PRIMARY KEY,
-                task_id TEXT NOT NULL,
-                start_time TEXT NOT NULL,
-                end_time TEXT,
-                duration_minutes INTEGER,
-                completed BOOLEAN NOT NULL DEFAULT 0,
-                created_at TEXT NOT NULL,
-                FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
-            )
-            "#,
-            [],
-        )?;
+    pub fn get_task_tags(&self, task_id: &str) -> AppResult<Vec<Tag>> {
+        let conn = self.conn.lock().unwrap();
+        find_task(&conn, task_id)?;
+        load_task_tags(&conn, task_id)
+    }
 
-        // Add new columns if they don't exist (migration)
-        conn.execute(
-            "ALTER TABLE task_sessions ADD COLUMN is_paused BOOLEAN NOT NULL DEFAULT 0",
-            [],
-        ).ok(); // Ignore error if column already exists
-        
-        conn.execute(
-            "ALTER TABLE task_sessions ADD COLUMN paused_at TEXT",
-            [],
-        ).ok(); // Ignore error if column already exists
-        
-        conn.execute(
-            "ALTER TABLE task_sessions ADD COLUMN total_paused_duration_ms INTEGER NOT NULL DEFAULT 0",
-            [],
-        ).ok(); // Ignore error if column already exists
+    pub fn set_task_tags(&self, req: SetTaskTagsRequest) -> AppResult<Vec<Tag>> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        find_task(&tx, &req.task_id)?;
+        for tag_id in &req.tag_ids {
+            find_tag(&tx, tag_id)?;
+        }
 
-        // 由于我们总是创建新的数据库，不需要复杂的迁移逻辑
-        println!("Database initialized successfully with correct column types");
+        tx.execute("DELETE FROM task_tags WHERE task_id = ?", [&req.task_id])?;
+        for tag_id in &req.tag_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO task_tags (task_id, tag_id) VALUES (?, ?)",
+                [&req.task_id, tag_id],
+            )?;
+        }
+        let tags = load_task_tags(&tx, &req.task_id)?;
+        tx.commit()?;
+        Ok(tags)
+    }
 
-        Ok(())
+    // Subtask operations
+    pub fn get_subtasks(&self, task_id: &str) -> AppResult<Vec<Subtask>> {
+        let conn = self.conn.lock().unwrap();
+        find_task(&conn, task_id)?;
+        load_subtasks(&conn, task_id)
     }
 
-    // Task Group operations
-    pub fn create_task_group(&self, req: CreateTaskGroupRequest) -> SqliteResult<TaskGroup> {
+    // 新的清单项追加在末尾
+    pub fn add_subtask(&self, req: AddSubtaskRequest) -> AppResult<Subtask> {
+        let title = validate_name("title", &req.title)?;
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
-        
+
         let conn = self.conn.lock().unwrap();
+        find_task(&conn, &req.task_id)?;
+        let position: i32 = conn.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM subtasks WHERE task_id = ?",
+            [&req.task_id],
+            |row| row.get(0),
+        )?;
         conn.execute(
             r#"
-            INSERT INTO task_groups (id, name, description, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO subtasks (id, task_id, title, position, completed_at, created_at, updated_at)
+            VALUES (?, ?, ?, ?, NULL, ?, ?)
             "#,
-            [&id, &req.name, &req.description.as_ref().map(|s| s.as_str()).unwrap_or("").to_string(), &now.to_rfc3339(), &now.to_rfc3339()],
+            rusqlite::params![id, req.task_id, title, position, now.to_rfc3339(), now.to_rfc3339()],
         )?;
 
-        Ok(TaskGroup {
+        Ok(Subtask {
             id,
-            name: req.name,
-            description: req.description,
+            task_id: req.task_id,
+            title,
+            position,
+            completed: false,
+            completed_at: None,
             created_at: now,
             updated_at: now,
         })
     }
 
-    pub fn get_task_groups(&self) -> SqliteResult<Vec<TaskGroup>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, created_at, updated_at FROM task_groups ORDER BY created_at DESC"
-        )?;
-        
-        let group_iter = stmt.query_map([], |row| {
-            Ok(TaskGroup {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            })
-        })?;
+    // 新顺序必须恰好包含该任务的全部清单项
+    pub fn reorder_subtasks(&self, req: ReorderSubtasksRequest) -> AppResult<Vec<Subtask>> {
+        let now = Utc::now();
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        find_task(&tx, &req.task_id)?;
 
-        let mut groups = Vec::new();
-        for group in group_iter {
-            groups.push(group?);
+        let mut current: Vec<String> = load_subtasks(&tx, &req.task_id)?
+            .into_iter()
+            .map(|subtask| subtask.id)
+            .collect();
+        let mut requested = req.subtask_ids.clone();
+        current.sort();
+        requested.sort();
+        if current != requested {
+            return Err(AppError::Validation(
+                "subtask_ids must list every subtask of the task exactly once".to_string(),
+            ));
         }
-        Ok(groups)
+
+        for (position, subtask_id) in req.subtask_ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE subtasks SET position = ?, updated_at = ? WHERE id = ?",
+                rusqlite::params![position as i32, now.to_rfc3339(), subtask_id],
+            )?;
+        }
+        let subtasks = load_subtasks(&tx, &req.task_id)?;
+        tx.commit()?;
+        Ok(subtasks)
     }
 
-    pub fn update_task_group(&self, id: &str, req: UpdateTaskGroupRequest) -> SqliteResult<TaskGroup> {
-        let now = Utc::now();
-        
+    pub fn toggle_subtask(&self, id: &str) -> AppResult<Subtask> {
+        let now = Utc::now().to_rfc3339();
         let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let updated = conn.execute(
             r#"
-            UPDATE task_groups 
-            SET name = COALESCE(?, name),
-                description = COALESCE(?, description),
-                updated_at = ?
-            WHERE id = ?
+            UPDATE subtasks
+            SET completed_at = CASE WHEN completed_at IS NULL THEN ?1 ELSE NULL END,
+                updated_at = ?1
+            WHERE id = ?2
             "#,
-            [&req.name.unwrap_or_default(), &req.description.unwrap_or_default(), &now.to_rfc3339(), id],
-        )?;
-
-        // Get updated task group
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, created_at, updated_at FROM task_groups WHERE id = ?"
+            rusqlite::params![now, id],
         )?;
-        let group = stmt.query_row([id], |row| {
-            Ok(TaskGroup {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(3)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(4)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            })
-        })?;
-
-        Ok(group)
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("subtask {}", id)));
+        }
+        find_subtask(&conn, id)
     }
 
-    pub fn delete_task_group(&self, id: &str) -> SqliteResult<()> {
+    pub fn delete_subtask(&self, id: &str) -> AppResult<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM task_groups WHERE id = ?", [id])?;
+        let deleted = conn.execute("DELETE FROM subtasks WHERE id = ?", [id])?;
+        if deleted == 0 {
+            return Err(AppError::NotFound(format!("subtask {}", id)));
+        }
         Ok(())
     }
 
-    // Task operations
-    pub fn create_task(&self, req: CreateTaskRequest) -> SqliteResult<Task> {
+    // Goal operations
+    pub fn create_goal(&self, req: CreateGoalRequest) -> AppResult<Goal> {
+        let name = validate_name("name", &req.name)?;
+        validate_goal(req.period, req.days_of_week.as_deref(), req.target_minutes)?;
+        stats::Zone::resolve(req.timezone.as_deref())?;
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
-        
+
         let conn = self.conn.lock().unwrap();
+        let (_, deleted_at) = find_goal_scope(&conn, req.scope, &req.scope_id)?;
+        if deleted_at.is_some() {
+            return Err(AppError::Conflict(format!("{} is in the trash", req.scope_id)));
+        }
+        let scope_column = |scope: GoalScope| (req.scope == scope).then_some(req.scope_id.as_str());
         conn.execute(
             r#"
-            INSERT INTO tasks (id, task_group_id, name, description, duration_minutes, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO goals (id, name, scope, task_id, task_group_id, tag_id, period, days_of_week, target_minutes, created_at, updated_at, timezone)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             rusqlite::params![
                 id,
-                req.task_group_id,
-                req.name,
-                req.description,
-                req.duration_minutes,
+                name,
+                req.scope.as_str(),
+                scope_column(GoalScope::Task),
+                scope_column(GoalScope::TaskGroup),
+                scope_column(GoalScope::Tag),
+                req.period.as_str(),
+                req.days_of_week.as_deref().map(recurrence::days_to_mask),
+                req.target_minutes,
                 now.to_rfc3339(),
-                now.to_rfc3339()
+                now.to_rfc3339(),
+                req.timezone
             ],
         )?;
 
-        Ok(Task {
+        Ok(Goal {
             id,
-            task_group_id: req.task_group_id,
-            name: req.name,
-            description: req.description,
-            duration_minutes: req.duration_minutes,
+            name,
+            scope: req.scope,
+            scope_id: req.scope_id,
+            period: req.period,
+            days_of_week: req.days_of_week,
+            target_minutes: req.target_minutes,
             created_at: now,
             updated_at: now,
+            timezone: req.timezone,
         })
     }
 
-    pub fn update_task(&self, id: &str, req: UpdateTaskRequest) -> SqliteResult<Task> {
-        let now = Utc::now();
-        
+    pub fn get_goals(&self) -> AppResult<Vec<Goal>> {
         let conn = self.conn.lock().unwrap();
-        conn.execute(
-            r#"
-            UPDATE tasks 
-            SET name = COALESCE(?, name),
-                description = COALESCE(?, description),
-                duration_minutes = ?,
-                updated_at = ?
-            WHERE id = ?
-            "#,
-            rusqlite::params![
-                req.name,
-                req.description,
-                req.duration_minutes,
-                now.to_rfc3339(),
-                id
-            ],
-        )?;
+        load_goals(&conn)
+    }
 
-        // Get updated task
-        let mut stmt = conn.prepare(
-            "SELECT id, task_group_id, name, description, duration_minutes, created_at, updated_at FROM tasks WHERE id = ?"
-        )?;
-        let task = stmt.query_row([id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                task_group_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                duration_minutes: row.get::<_, Option<i32>>(4)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            })
-        })?;
+    // 合并后的周期、星期和目标时长需要整体有效
+    pub fn update_goal(&self, id: &str, req: UpdateGoalRequest) -> AppResult<Goal> {
+        let name = validate_name_patch("name", req.name)?;
+        if matches!(req.period, Patch::Null) {
+            return Err(AppError::Validation("period cannot be null".to_string()));
+        }
+        if matches!(req.target_minutes, Patch::Null) {
+            return Err(AppError::Validation("target_minutes cannot be null".to_string()));
+        }
+        if let Patch::Value(timezone) = &req.timezone {
+            stats::Zone::resolve(Some(timezone))?;
+        }
+
+        let conn = self.conn.lock().unwrap();
+        let goal = find_goal(&conn, id)?;
+        let period = req.period.value().copied().unwrap_or(goal.period);
+        let days_of_week = match &req.days_of_week {
+            Patch::Absent => goal.days_of_week.clone(),
+            Patch::Null => None,
+            Patch::Value(days) => Some(days.clone()),
+        };
+        let target_minutes = req.target_minutes.value().copied().unwrap_or(goal.target_minutes);
+        validate_goal(period, days_of_week.as_deref(), target_minutes)?;
 
-        Ok(task)
+        let mut update = PatchUpdate::default();
+        update.patch("name", name);
+        update.patch("period", req.period.map(|period| period.as_str().to_string()));
+        update.patch("days_of_week", req.days_of_week.map(|days| recurrence::days_to_mask(&days)));
+        update.patch("target_minutes", req.target_minutes);
+        update.patch("timezone", req.timezone);
+        update.execute(&conn, "goals", id, Utc::now())?;
+        find_goal(&conn, id)
     }
 
-    pub fn delete_task(&self, id: &str) -> SqliteResult<()> {
+    pub fn delete_goal(&self, id: &str) -> AppResult<()> {
         let conn = self.conn.lock().unwrap();
-        conn.execute("DELETE FROM tasks WHERE id = ?", [id])?;
+        let deleted = conn.execute("DELETE FROM goals WHERE id = ?", [id])?;
+        if deleted == 0 {
+            return Err(AppError::NotFound(format!("goal {}", id)));
+        }
         Ok(())
     }
 
+    // 当前周期加上最近的若干个历史周期；按星期设定的每日目标跳过不计的日子
+    pub fn get_goal_progress(&self, req: GoalProgressRequest) -> AppResult<Vec<GoalProgress>> {
+        let include_manual = req.include_manual.unwrap_or(true);
+        let history_periods = req.history_periods.unwrap_or(DEFAULT_GOAL_HISTORY);
+        if history_periods > MAX_GOAL_HISTORY {
+            return Err(AppError::Validation(format!("history_periods must be at most {}", MAX_GOAL_HISTORY)));
+        }
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        let goals = match &req.goal_id {
+            Some(goal_id) => vec![find_goal(&conn, goal_id)?],
+            None => load_goals(&conn)?,
+        };
+
+        let mut progress = Vec::new();
+        for goal in goals {
+            let zone = goal_zone(&goal)?;
+            let today = zone.local_date(now);
+            let current = stats::bucket_start(today, goal_bucket(goal.period));
+            let mut history = Vec::new();
+            let mut period_start = current;
+            // 连续一周都不计的日子说明之后也不会再有，提前结束以免死循环
+            let mut skipped = 0;
+            while history.len() < history_periods as usize && skipped < 7 {
+                period_start = match goal.period {
+                    GoalPeriod::Day => period_start - chrono::Duration::days(1),
+                    GoalPeriod::Week => period_start - chrono::Duration::days(7),
+                };
+                if goal_active_on(&goal, period_start) {
+                    history.push(period_start);
+                    skipped = 0;
+                } else {
+                    skipped += 1;
+                }
+            }
+
+            let first_period = history.last().copied().unwrap_or(current);
+            let sessions = load_focus_sessions(
+                &conn,
+                include_manual,
+                zone.start_of_day(first_period),
+                zone.start_of_day(today + chrono::Duration::days(1)),
+            )?;
+            let task_ids = load_goal_task_ids(&conn, &goal)?;
+            let totals = goal_period_totals(&sessions, &task_ids, zone, goal.period);
+            let period_progress = |period_start: NaiveDate| {
+                goal_period_progress(&goal, period_start, totals.get(&period_start).copied().unwrap_or(0))
+            };
+            let current = goal_active_on(&goal, current).then(|| period_progress(current));
+            let history = history.into_iter().map(period_progress).collect();
+            let (scope_name, _) = find_goal_scope(&conn, goal.scope, &goal.scope_id)?;
+            progress.push(GoalProgress {
+                goal,
+                scope_name,
+                current,
+                history,
+            });
+        }
+        Ok(progress)
+    }
+
+    /// Goals whose current period reached the target only with the given
+    /// finished session, so a goal is reported once per period even if more
+    /// sessions follow.
+    pub fn goals_met_by(&self, session_id: &str) -> AppResult<Vec<GoalMet>> {
+        let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, session_id)?;
+        let end_time = match session.end_time {
+            Some(end_time) if session.kind == SessionKind::Focus => end_time,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut met = Vec::new();
+        for goal in load_goals(&conn)? {
+            let zone = goal_zone(&goal)?;
+            let today = zone.local_date(end_time);
+            if !goal_active_on(&goal, today) {
+                continue;
+            }
+            let task_ids = load_goal_task_ids(&conn, &goal)?;
+            if !task_ids.contains(&session.task_id) {
+                continue;
+            }
+
+            let period_start = stats::bucket_start(today, goal_bucket(goal.period));
+            let range_start = zone.start_of_day(period_start);
+            let range_end = zone.start_of_day(stats::next_bucket(period_start, goal_bucket(goal.period)));
+            let focused_seconds: i64 = load_focus_sessions(&conn, true, range_start, range_end)?
+                .iter()
+                .filter(|row| task_ids.contains(&row.task_id))
+                .map(|row| row.clipped.duration_seconds)
+                .sum();
+            let session_seconds = stats::ClippedSession::clip(
+                session.start_time,
+                end_time,
+                session.duration_seconds.unwrap_or(0),
+                range_start,
+                range_end,
+            )
+            .map(|clipped| clipped.duration_seconds)
+            .unwrap_or(0);
+
+            let target_seconds = goal.target_minutes as i64 * 60;
+            if focused_seconds >= target_seconds && focused_seconds - session_seconds < target_seconds {
+                let (scope_name, _) = find_goal_scope(&conn, goal.scope, &goal.scope_id)?;
+                met.push(GoalMet {
+                    period: goal_period_progress(&goal, period_start, focused_seconds),
+                    goal,
+                    scope_name,
+                    session_id: session.id.clone(),
+                });
+            }
+        }
+        Ok(met)
+    }
+
     // Session operations
-    pub fn start_session(&self, req: StartSessionRequest) -> SqliteResult<TaskSession> {
+    pub fn start_session(&self, req: StartSessionRequest) -> AppResult<TaskSession> {
         let id = Uuid::new_v4().to_string();
         let now = Utc::now();
         
         println!("Starting session for task: {}", req.task_id);
         
-        let conn = self.conn.lock().unwrap();
-        conn.execute(
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let task = find_task(&tx, &req.task_id)?;
+        let group = find_task_group(&tx, &task.task_group_id)?;
+        if task.deleted_at.is_some() || group.deleted_at.is_some() {
+            return Err(AppError::Conflict(format!("task {} is in the trash", task.id)));
+        }
+        if task.archived_at.is_some() || group.archived_at.is_some() {
+            return Err(AppError::Conflict(format!("task {} is archived", task.id)));
+        }
+
+        // 同一时间只允许一个进行中的会话：拒绝，或在同一事务中中断上一个会话
+        if let Some(active) = find_active_session(&tx)? {
+            match req.on_active.unwrap_or(ActiveSessionPolicy::Reject) {
+                ActiveSessionPolicy::Reject => {
+                    return Err(AppError::SessionAlreadyActive(Box::new(active)));
+                }
+                ActiveSessionPolicy::Switch => {
+                    println!("Switching away from active session: {}", active.session.id);
+                    close_session(&tx, &active.session.id, None, Some(SessionOutcome::Interrupted), now)?;
+                }
+            }
+        }
+
+        // 番茄钟任务未指定阶段时，继续当前循环的下一阶段
+        let kind = match (req.kind, &task.pomodoro) {
+            (Some(kind), _) => kind,
+            (None, Some(config)) => load_pomodoro_state(&tx, &task, config)?.next_kind,
+            (None, None) => SessionKind::Focus,
+        };
+        let planned_minutes = planned_minutes(&task, kind)?;
+
+        tx.execute(
             r#"
-            INSERT INTO task_sessions (id, task_id, start_time, completed, is_paused, total_paused_duration_ms, created_at)
-            VALUES (?, ?, ?, 0, 0, 0, ?)
+            INSERT INTO task_sessions (id, task_id, start_time, completed, kind, planned_minutes, is_paused, total_paused_duration_ms, created_at)
+            VALUES (?, ?, ?, 0, ?, ?, 0, 0, ?)
             "#,
-            [&id, &req.task_id, &now.to_rfc3339(), &now.to_rfc3339()],
+            rusqlite::params![id, req.task_id, now.to_rfc3339(), kind.as_str(), planned_minutes, now.to_rfc3339()],
         )?;
+        tx.commit()?;
 
         println!("Session created with ID: {}", id);
 
@@ -291,288 +2086,827 @@ impl Database {
             start_time: now,
             end_time: None,
             duration_minutes: None,
+            duration_seconds: None,
             completed: false,
+            outcome: None,
+            kind,
+            planned_minutes,
             is_paused: false,
             paused_at: None,
             total_paused_duration_ms: 0,
+            needs_review: false,
+            is_manual: false,
             created_at: now,
         })
     }
 
-    pub fn end_session(&self, req: EndSessionRequest) -> SqliteResult<TaskSession> {
+    pub fn end_session(&self, req: EndSessionRequest) -> AppResult<TaskSession> {
+        let now = Utc::now();
+        
+        let conn = self.conn.lock().unwrap();
+        close_session(&conn, &req.session_id, req.duration_minutes, req.outcome, now)
+    }
+
+    pub fn create_manual_session(&self, req: CreateManualSessionRequest) -> AppResult<TaskSession> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        let task = find_untrashed_task(&conn, &req.task_id)?;
+        let duration_seconds = req
+            .duration_minutes
+            .map(|minutes| minutes as i64 * 60)
+            .unwrap_or((req.end_time - req.start_time).num_seconds());
+        validate_session_times(req.start_time, req.end_time, duration_seconds, now)?;
+        ensure_no_overlap(&conn, None, req.start_time, req.end_time, now)?;
+
+        let outcome = req.outcome.unwrap_or(SessionOutcome::Completed);
+        // 未计入专注的部分按暂停时间记录，使 开始 + 暂停 + 专注 = 结束
+        let total_paused_duration_ms = (req.end_time - req.start_time).num_milliseconds() - duration_seconds * 1000;
+        conn.execute(
+            r#"
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, duration_seconds,
+                completed, outcome, kind, planned_minutes, is_paused, total_paused_duration_ms, is_manual, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 0, ?, 1, ?)
+            "#,
+            rusqlite::params![
+                id,
+                req.task_id,
+                req.start_time.to_rfc3339(),
+                req.end_time.to_rfc3339(),
+                duration_seconds / 60,
+                duration_seconds,
+                outcome == SessionOutcome::Completed,
+                outcome.as_str(),
+                SessionKind::Focus.as_str(),
+                task.duration_minutes,
+                total_paused_duration_ms,
+                now.to_rfc3339()
+            ],
+        )?;
+
+        println!("Manual session created with ID: {}", id);
+
+        find_session(&conn, &id)
+    }
+
+    // 修改过的会话不再是计时器记录的数据，同样标记为手动录入
+    pub fn update_session(&self, id: &str, req: UpdateSessionRequest) -> AppResult<TaskSession> {
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, id)?;
+        let current_end = session
+            .end_time
+            .ok_or_else(|| AppError::Conflict(format!("session {} is still running", id)))?;
+        if let Some(task_id) = &req.task_id {
+            find_untrashed_task(&conn, task_id)?;
+        }
+
+        let start_time = req.start_time.unwrap_or(session.start_time);
+        let end_time = req.end_time.unwrap_or(current_end);
+        let times_changed = start_time != session.start_time || end_time != current_end;
+        let duration_seconds = match req.duration_minutes {
+            Some(minutes) => minutes as i64 * 60,
+            None if times_changed => ((end_time - start_time).num_milliseconds() - session.total_paused_duration_ms).max(0) / 1000,
+            None => session.duration_seconds.unwrap_or(0),
+        };
+        validate_session_times(start_time, end_time, duration_seconds, now)?;
+        ensure_no_overlap(&conn, Some(id), start_time, end_time, now)?;
+
+        let outcome = req.outcome.or(session.outcome).unwrap_or(SessionOutcome::Completed);
+        let total_paused_duration_ms = (end_time - start_time).num_milliseconds() - duration_seconds * 1000;
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET task_id = ?,
+                start_time = ?,
+                end_time = ?,
+                duration_minutes = ?,
+                duration_seconds = ?,
+                total_paused_duration_ms = ?,
+                completed = ?,
+                outcome = ?,
+                needs_review = 0,
+                is_manual = 1
+            WHERE id = ?
+            "#,
+            rusqlite::params![
+                req.task_id.unwrap_or(session.task_id),
+                start_time.to_rfc3339(),
+                end_time.to_rfc3339(),
+                duration_seconds / 60,
+                duration_seconds,
+                total_paused_duration_ms,
+                outcome == SessionOutcome::Completed,
+                outcome.as_str(),
+                id
+            ],
+        )?;
+
+        find_session(&conn, id)
+    }
+
+    pub fn delete_session(&self, id: &str) -> AppResult<()> {
+        let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, id)?;
+        if session.end_time.is_none() {
+            return Err(AppError::Conflict(format!("session {} is still running", id)));
+        }
+        conn.execute("DELETE FROM task_sessions WHERE id = ?", [id])?;
+        Ok(())
+    }
+
+    pub fn list_sessions(&self, req: ListSessionsRequest) -> AppResult<SessionPage> {
+        let descending = req.sort.unwrap_or(SortDirection::Desc) == SortDirection::Desc;
+        let limit = req.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        // 与统计相同，日期按所在时区的整天计算，区间为 [start, end)
+        let (range_start, range_end) = match (&req.start_date, &req.end_date) {
+            (Some(start_date), Some(end_date)) => {
+                let (range_start, range_end) = stats::parse_range(start_date, end_date, zone)?;
+                (Some(range_start), Some(range_end))
+            }
+            (start_date, end_date) => (
+                start_date.as_deref().map(|value| stats::parse_bound(value, false, zone)).transpose()?,
+                end_date.as_deref().map(|value| stats::parse_bound(value, true, zone)).transpose()?,
+            ),
+        };
+
+        let mut conditions = Vec::new();
+        let mut params: Vec<String> = Vec::new();
+        if let Some(range_start) = range_start {
+            conditions.push("s.start_time >= ?");
+            params.push(range_start.to_rfc3339());
+        }
+        if let Some(range_end) = range_end {
+            conditions.push("s.start_time < ?");
+            params.push(range_end.to_rfc3339());
+        }
+        if let Some(task_id) = req.task_id {
+            conditions.push("s.task_id = ?");
+            params.push(task_id);
+        }
+        if let Some(task_group_id) = req.task_group_id {
+            conditions.push("t.task_group_id = ?");
+            params.push(task_group_id);
+        }
+        if let Some(tag_id) = req.tag_id {
+            conditions.push("s.task_id IN (SELECT task_id FROM task_tags WHERE tag_id = ?)");
+            params.push(tag_id);
+        }
+        if let Some(outcome) = req.outcome {
+            conditions.push("s.outcome = ?");
+            params.push(outcome.as_str().to_string());
+        }
+        // 游标为上一页最后一条的 "start_time|id"，按 (start_time, id) 做键集分页
+        if let Some(cursor) = req.cursor {
+            let (start_time, id) = cursor
+                .split_once('|')
+                .ok_or_else(|| AppError::Validation(format!("invalid cursor: {}", cursor)))?;
+            conditions.push(if descending {
+                "(s.start_time < ? OR (s.start_time = ? AND s.id < ?))"
+            } else {
+                "(s.start_time > ? OR (s.start_time = ? AND s.id > ?))"
+            });
+            params.extend([start_time.to_string(), start_time.to_string(), id.to_string()]);
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let direction = if descending { "DESC" } else { "ASC" };
+
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {}, {}, {}
+            FROM task_sessions s
+            JOIN tasks t ON s.task_id = t.id
+            JOIN task_groups tg ON t.task_group_id = tg.id
+            {}
+            ORDER BY s.start_time {}, s.id {}
+            LIMIT {}
+            "#,
+            prefixed("s", SESSION_COLUMNS),
+            prefixed("t", TASK_COLUMNS),
+            prefixed("tg", TASK_GROUP_COLUMNS),
+            where_clause,
+            direction,
+            direction,
+            limit + 1,
+        ))?;
+
+        let session_iter = stmt.query_map(rusqlite::params_from_iter(params), map_session_detail)?;
+
+        let mut sessions = Vec::new();
+        for session in session_iter {
+            sessions.push(session?);
+        }
+
+        // 多取一条用来判断是否还有下一页
+        let next_cursor = if sessions.len() > limit as usize {
+            sessions.truncate(limit as usize);
+            sessions
+                .last()
+                .map(|last| format!("{}|{}", last.session.start_time.to_rfc3339(), last.session.id))
+        } else {
+            None
+        };
+
+        Ok(SessionPage { sessions, next_cursor })
+    }
+
+    pub fn get_recovered_sessions(&self) -> AppResult<Vec<SessionDetail>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {}, {}, {}
+            FROM task_sessions s
+            JOIN tasks t ON s.task_id = t.id
+            JOIN task_groups tg ON t.task_group_id = tg.id
+            WHERE s.needs_review = 1
+            ORDER BY s.start_time DESC
+            "#,
+            prefixed("s", SESSION_COLUMNS),
+            prefixed("t", TASK_COLUMNS),
+            prefixed("tg", TASK_GROUP_COLUMNS),
+        ))?;
+
+        let session_iter = stmt.query_map([], map_session_detail)?;
+
+        let mut sessions = Vec::new();
+        for session in session_iter {
+            sessions.push(session?);
+        }
+        Ok(sessions)
+    }
+
+    pub fn resolve_recovered_session(&self, req: ResolveRecoveredSessionRequest) -> AppResult<TaskSession> {
+        let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, &req.session_id)?;
+        if !session.needs_review {
+            return Err(AppError::Conflict(format!("session {} is not awaiting review", session.id)));
+        }
+
+        let now = Utc::now();
+        let outcome = req.outcome.or(session.outcome).unwrap_or(SessionOutcome::AutoClosed);
+        let duration_seconds = match req.duration_minutes {
+            Some(minutes) if minutes <= 0 => {
+                return Err(AppError::Validation("duration_minutes must be positive".to_string()));
+            }
+            Some(minutes) => minutes as i64 * 60,
+            None => session.duration_seconds.unwrap_or(0),
+        };
+        // 确认后的结束时间同样不能晚于现在，也不能与其他会话重叠
+        let end_time = session.start_time
+            + chrono::Duration::milliseconds(session.total_paused_duration_ms + duration_seconds * 1000);
+        validate_session_times(session.start_time, end_time, duration_seconds, now)?;
+        ensure_no_overlap(&conn, Some(&session.id), session.start_time, end_time, now)?;
+
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET end_time = ?,
+                duration_minutes = ?,
+                duration_seconds = ?,
+                completed = ?,
+                outcome = ?,
+                needs_review = 0
+            WHERE id = ?
+            "#,
+            rusqlite::params![
+                end_time.to_rfc3339(),
+                duration_seconds / 60,
+                duration_seconds,
+                outcome == SessionOutcome::Completed,
+                outcome.as_str(),
+                req.session_id
+            ],
+        )?;
+
+        find_session(&conn, &req.session_id)
+    }
+
+    pub fn get_pomodoro_state(&self, task_id: &str) -> AppResult<PomodoroState> {
+        let conn = self.conn.lock().unwrap();
+        let task = find_task(&conn, task_id)?;
+        let config = task
+            .pomodoro
+            .as_ref()
+            .ok_or_else(|| AppError::Conflict(format!("task {} has no Pomodoro settings", task.id)))?;
+        load_pomodoro_state(&conn, &task, config)
+    }
+
+    pub fn reset_pomodoro_cycle(&self, task_id: &str) -> AppResult<PomodoroState> {
+        {
+            let conn = self.conn.lock().unwrap();
+            conn.execute("DELETE FROM pomodoro_state WHERE task_id = ?", [task_id])?;
+        }
+        self.get_pomodoro_state(task_id)
+    }
+
+    pub fn pause_session(&self, req: PauseSessionRequest) -> AppResult<TaskSession> {
+        let now = Utc::now();
+
+        let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, &req.session_id)?;
+        if session.end_time.is_some() {
+            return Err(AppError::Conflict(format!("session {} has already ended", session.id)));
+        }
+        if session.is_paused {
+            return Err(AppError::Conflict(format!("session {} is already paused", session.id)));
+        }
+
+        conn.execute(
+            "UPDATE task_sessions SET is_paused = 1, paused_at = ? WHERE id = ?",
+            rusqlite::params![now.to_rfc3339(), req.session_id],
+        )?;
+
+        println!("Session paused: {}", req.session_id);
+
+        find_session(&conn, &req.session_id)
+    }
+
+    pub fn resume_session(&self, req: ResumeSessionRequest) -> AppResult<TaskSession> {
         let now = Utc::now();
-        
+
         let conn = self.conn.lock().unwrap();
+        let session = find_session(&conn, &req.session_id)?;
+        if session.end_time.is_some() {
+            return Err(AppError::Conflict(format!("session {} has already ended", session.id)));
+        }
+        let paused_at = match (session.is_paused, session.paused_at) {
+            (true, Some(paused_at)) => paused_at,
+            _ => return Err(AppError::Conflict(format!("session {} is not paused", session.id))),
+        };
+
+        // 时钟回拨时不计入负的暂停时长
+        let paused_ms = (now - paused_at).num_milliseconds().max(0);
         conn.execute(
             r#"
-            UPDATE task_sessions 
-            SET end_time = ?, duration_minutes = ?, completed = 1
+            UPDATE task_sessions
+            SET is_paused = 0,
+                paused_at = NULL,
+                total_paused_duration_ms = total_paused_duration_ms + ?
             WHERE id = ?
             "#,
-            rusqlite::params![
-                now.to_rfc3339(),
-                req.duration_minutes,
-                req.session_id
-            ],
+            rusqlite::params![paused_ms, req.session_id],
         )?;
 
-        // Get updated session
-        let mut stmt = conn.prepare(
-            "SELECT id, task_id, start_time, end_time, duration_minutes, completed, is_paused, paused_at, total_paused_duration_ms, created_at FROM task_sessions WHERE id = ?"
-        )?;
-        let session = stmt.query_row([&req.session_id], |row| {
-            Ok(TaskSession {
-                id: row.get(0)?,
-                task_id: row.get(1)?,
-                start_time: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-                end_time: row.get::<_, Option<String>>(3)?
-                    .map(|s| DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
-                duration_minutes: row.get(4)?,
-                completed: row.get(5)?,
-                is_paused: row.get(6)?,
-                paused_at: row.get::<_, Option<String>>(7)?
-                    .map(|s| DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
-                total_paused_duration_ms: row.get(8)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            })
-        })?;
+        println!("Session resumed: {} (paused for {} ms)", req.session_id, paused_ms);
 
-        Ok(session)
+        find_session(&conn, &req.session_id)
     }
 
-    pub fn get_active_session(&self) -> SqliteResult<Option<ActiveSession>> {
+    pub fn get_active_session(&self) -> AppResult<Option<ActiveSession>> {
         let conn = self.conn.lock().unwrap();
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT 
-                s.id as session_id,
-                s.task_id,
-                s.start_time,
-                s.end_time,
-                s.duration_minutes,
-                s.completed,
-                s.is_paused,
-                s.paused_at,
-                s.total_paused_duration_ms,
-                s.created_at as session_created_at,
-                t.id as task_id_2,
-                t.task_group_id,
-                t.name as task_name,
-                t.description as task_description,
-                t.duration_minutes as task_duration_minutes,
-                t.created_at as task_created_at,
-                t.updated_at as task_updated_at,
-                tg.id as group_id,
-                tg.name as group_name,
-                tg.description as group_description,
-                tg.created_at as group_created_at,
-                tg.updated_at as group_updated_at
-            FROM task_sessions s
-            JOIN tasks t ON s.task_id = t.id
-            JOIN task_groups tg ON t.task_group_id = tg.id
-            WHERE s.end_time IS NULL
-            ORDER BY s.start_time DESC
-            LIMIT 1
-            "#
-        )?;
-
-        let result = stmt.query_row([], |row| {
-            Ok(ActiveSession {
-                session: TaskSession {
-                    id: row.get(0)?,
-                    task_id: row.get(1)?,
-                    start_time: DateTime::parse_from_rfc3339(&row.get::<_, String>(2)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                    end_time: row.get::<_, Option<String>>(3)?
-                        .map(|s| DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
-                    duration_minutes: row.get(4)?,
-                    completed: row.get(5)?,
-                    is_paused: row.get(6)?,
-                    paused_at: row.get::<_, Option<String>>(7)?
-                        .map(|s| DateTime::parse_from_rfc3339(&s).unwrap().with_timezone(&Utc)),
-                    total_paused_duration_ms: row.get(8)?,
-                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(9)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                },
-                task: Task {
-                    id: row.get(10)?, // t.id as task_id_2
-                    task_group_id: row.get(11)?,
-                    name: row.get(12)?,
-                    description: row.get(13)?,
-                    duration_minutes: row.get(14)?,
-                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(15)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                    updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(16)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                },
-                task_group: TaskGroup {
-                    id: row.get(17)?,
-                    name: row.get(18)?,
-                    description: row.get(19)?,
-                    created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(20)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                    updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(21)?)
-                        .unwrap()
-                        .with_timezone(&Utc),
-                },
-            })
-        });
+        let result = find_active_session(&conn)?;
 
-        match result {
-            Ok(session) => {
-                println!("Found active session: {}", session.session.id);
-                Ok(Some(session))
-            },
-            Err(rusqlite::Error::QueryReturnedNoRows) => {
-                println!("No active session found");
-                Ok(None)
-            },
-            Err(e) => Err(e),
+        match &result {
+            Some(session) => println!("Found active session: {}", session.session.id),
+            None => println!("No active session found"),
         }
+        Ok(result)
     }
 
     // Statistics operations
-    pub fn get_statistics(&self, req: StatisticsRequest) -> SqliteResult<StatisticsResponse> {
-        // 直接使用字符串格式的日期，不需要转换
-        let start_date = &req.start_date;
-        let end_date = &req.end_date;
-        
-        println!("Getting statistics for date range: {} to {}", start_date, end_date);
-        
-        // Get task statistics
+    pub fn get_statistics(&self, req: StatisticsRequest) -> AppResult<StatisticsResponse> {
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        let (range_start, range_end) = stats::parse_range(&req.start_date, &req.end_date, zone)?;
+        let include_manual = req.include_manual.unwrap_or(true);
+
+        println!("Getting statistics for range: {} to {}", range_start, range_end);
+
         let conn = self.conn.lock().unwrap();
-        
-        // 添加一些调试信息来检查数据库中的实际数据
-        let mut debug_stmt = conn.prepare("SELECT COUNT(*) as total_sessions FROM task_sessions WHERE end_time IS NOT NULL")?;
-        let total_sessions: i32 = debug_stmt.query_row([], |row| row.get(0))?;
-        println!("Total completed sessions in database: {}", total_sessions);
-        
-        let mut debug_stmt2 = conn.prepare("SELECT COUNT(*) as sessions_in_range FROM task_sessions WHERE end_time IS NOT NULL AND start_time >= ? AND start_time <= ?")?;
-        let sessions_in_range: i32 = debug_stmt2.query_row([start_date, end_date], |row| row.get(0))?;
-        println!("Sessions in date range: {}", sessions_in_range);
+        let sessions = load_focus_sessions(&conn, include_manual, range_start, range_end)?;
+        println!("Sessions in date range: {}", sessions.len());
+
+        let mut task_tallies: HashMap<String, stats::Tally> = HashMap::new();
+        let mut group_tallies: HashMap<String, stats::Tally> = HashMap::new();
+        for session in &sessions {
+            let seconds = session.clipped.duration_seconds;
+            task_tallies
+                .entry(session.task_id.clone())
+                .or_default()
+                .add(session.outcome, session.is_manual, seconds);
+            group_tallies
+                .entry(session.task_group_id.clone())
+                .or_default()
+                .add(session.outcome, session.is_manual, seconds);
+        }
+
+        // Get task statistics
+        // 已归档或在回收站中的任务仍计入历史统计，但没有会话时不再列出
         let mut stmt = conn.prepare(
             r#"
-            SELECT 
-                t.id as task_id,
-                t.name as task_name,
-                tg.name as task_group_name,
-                COUNT(s.id) as total_sessions,
-                COALESCE(SUM(s.duration_minutes), 0) as total_duration_minutes,
-                COUNT(CASE WHEN s.completed = 1 THEN 1 END) as completed_sessions
+            SELECT t.id, t.name, tg.name, tg.color,
+                (t.archived_at IS NOT NULL OR t.deleted_at IS NOT NULL
+                    OR tg.archived_at IS NOT NULL OR tg.deleted_at IS NOT NULL)
             FROM tasks t
             JOIN task_groups tg ON t.task_group_id = tg.id
-            LEFT JOIN task_sessions s ON t.id = s.task_id 
-                AND s.start_time >= ? AND s.start_time <= ?
-            GROUP BY t.id, t.name, tg.name
-            ORDER BY total_duration_minutes DESC
             "#
         )?;
-
-        let task_iter = stmt.query_map([start_date, end_date], |row| {
-            let total_sessions: i32 = row.get(3)?;
-            let completed_sessions: i32 = row.get(5)?;
-            let completion_rate = if total_sessions > 0 {
-                completed_sessions as f64 / total_sessions as f64
-            } else {
-                0.0
-            };
-
-            Ok(TaskStatistics {
-                task_id: row.get(0)?,
-                task_name: row.get(1)?,
-                task_group_name: row.get(2)?,
-                total_sessions,
-                total_duration_minutes: row.get(4)?,
-                completed_sessions,
-                completion_rate,
-            })
+        let task_iter = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, bool>(4)?,
+            ))
         })?;
 
         let mut task_statistics = Vec::new();
-        for stat in task_iter {
-            task_statistics.push(stat?);
+        for task in task_iter {
+            let (task_id, task_name, task_group_name, task_group_color, inactive) = task?;
+            let tally = match task_tallies.remove(&task_id) {
+                Some(tally) => tally,
+                None if inactive => continue,
+                None => stats::Tally::default(),
+            };
+            task_statistics.push(TaskStatistics {
+                task_id,
+                task_name,
+                task_group_name,
+                task_group_color,
+                total_sessions: tally.total_sessions,
+                total_duration_minutes: (tally.total_duration_seconds / 60) as i32,
+                total_duration_seconds: tally.total_duration_seconds,
+                completed_sessions: tally.completed_sessions,
+                abandoned_sessions: tally.abandoned_sessions,
+                interrupted_sessions: tally.interrupted_sessions,
+                auto_closed_sessions: tally.auto_closed_sessions,
+                manual_sessions: tally.manual_sessions,
+                completion_rate: completion_rate(tally.completed_sessions, tally.total_sessions, tally.auto_closed_sessions),
+            });
         }
+        task_statistics.sort_by_key(|stat| std::cmp::Reverse(stat.total_duration_seconds));
 
         // Get task group statistics
         let mut stmt = conn.prepare(
             r#"
-            SELECT 
-                tg.id as task_group_id,
-                tg.name as task_group_name,
-                COUNT(DISTINCT t.id) as total_tasks,
-                COUNT(s.id) as total_sessions,
-                COALESCE(SUM(s.duration_minutes), 0) as total_duration_minutes,
-                COUNT(CASE WHEN s.completed = 1 THEN 1 END) as completed_sessions
+            SELECT tg.id, tg.name, tg.parent_id, COUNT(t.id),
+                (tg.archived_at IS NOT NULL OR tg.deleted_at IS NOT NULL), tg.color
             FROM task_groups tg
             LEFT JOIN tasks t ON tg.id = t.task_group_id
-            LEFT JOIN task_sessions s ON t.id = s.task_id 
-                AND s.start_time >= ? AND s.start_time <= ?
             GROUP BY tg.id, tg.name
-            ORDER BY total_duration_minutes DESC
             "#
         )?;
+        let groups = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, i32>(3)?,
+                    row.get::<_, bool>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })?
+            .collect::<SqliteResult<Vec<_>>>()?;
 
-        let group_iter = stmt.query_map([start_date, end_date], |row| {
-            let total_sessions: i32 = row.get(3)?;
-            let completed_sessions: i32 = row.get(5)?;
-            let completion_rate = if total_sessions > 0 {
-                completed_sessions as f64 / total_sessions as f64
-            } else {
-                0.0
+        // 把每个任务组自身的统计累加到它和所有祖先上
+        let parents: HashMap<&str, Option<&str>> = groups
+            .iter()
+            .map(|(id, _, parent_id, _, _, _)| (id.as_str(), parent_id.as_deref()))
+            .collect();
+        let mut rollups: HashMap<&str, (i32, stats::Tally)> = HashMap::new();
+        for (id, _, _, total_tasks, _, _) in &groups {
+            let direct = group_tallies.get(id).cloned().unwrap_or_default();
+            let mut current = Some(id.as_str());
+            let mut depth = 0;
+            while let Some(ancestor) = current {
+                let rollup = rollups.entry(ancestor).or_default();
+                rollup.0 += total_tasks;
+                rollup.1.merge(&direct);
+                current = parents.get(ancestor).copied().flatten();
+                depth += 1;
+                if depth > groups.len() {
+                    break; // 父链存在环时不再继续向上
+                }
+            }
+        }
+
+        let mut task_group_statistics = Vec::new();
+        for (task_group_id, task_group_name, parent_id, total_tasks, inactive, color) in &groups {
+            let (rollup_tasks, rollup) = rollups.remove(task_group_id.as_str()).unwrap_or_default();
+            let tally = match group_tallies.remove(task_group_id) {
+                Some(tally) => tally,
+                None if *inactive && rollup.total_sessions == 0 => continue,
+                None => stats::Tally::default(),
             };
+            task_group_statistics.push(TaskGroupStatistics {
+                task_group_id: task_group_id.clone(),
+                task_group_name: task_group_name.clone(),
+                parent_id: parent_id.clone(),
+                color: color.clone(),
+                total_tasks: *total_tasks,
+                total_sessions: tally.total_sessions,
+                total_duration_minutes: (tally.total_duration_seconds / 60) as i32,
+                total_duration_seconds: tally.total_duration_seconds,
+                completed_sessions: tally.completed_sessions,
+                abandoned_sessions: tally.abandoned_sessions,
+                interrupted_sessions: tally.interrupted_sessions,
+                auto_closed_sessions: tally.auto_closed_sessions,
+                manual_sessions: tally.manual_sessions,
+                completion_rate: completion_rate(tally.completed_sessions, tally.total_sessions, tally.auto_closed_sessions),
+                rollup_tasks,
+                rollup_sessions: rollup.total_sessions,
+                rollup_duration_minutes: (rollup.total_duration_seconds / 60) as i32,
+                rollup_duration_seconds: rollup.total_duration_seconds,
+                rollup_completed_sessions: rollup.completed_sessions,
+                rollup_completion_rate: completion_rate(rollup.completed_sessions, rollup.total_sessions, rollup.auto_closed_sessions),
+            });
+        }
+        task_group_statistics.sort_by_key(|stat| std::cmp::Reverse(stat.total_duration_seconds));
 
-            Ok(TaskGroupStatistics {
-                task_group_id: row.get(0)?,
-                task_group_name: row.get(1)?,
-                total_tasks: row.get(2)?,
-                total_sessions,
-                total_duration_minutes: row.get(4)?,
-                completed_sessions,
-                completion_rate,
-            })
+        // Get tag statistics
+        let mut task_tags: HashMap<String, Vec<String>> = HashMap::new();
+        let mut stmt = conn.prepare("SELECT task_id, tag_id FROM task_tags")?;
+        let link_iter = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for link in link_iter {
+            let (task_id, tag_id) = link?;
+            task_tags.entry(task_id).or_default().push(tag_id);
+        }
+
+        let mut tag_tallies: HashMap<String, stats::Tally> = HashMap::new();
+        for session in &sessions {
+            for tag_id in task_tags.get(&session.task_id).into_iter().flatten() {
+                tag_tallies
+                    .entry(tag_id.clone())
+                    .or_default()
+                    .add(session.outcome, session.is_manual, session.clipped.duration_seconds);
+            }
+        }
+
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT tg.id, tg.name, COUNT(tt.task_id)
+            FROM tags tg
+            LEFT JOIN task_tags tt ON tt.tag_id = tg.id
+            GROUP BY tg.id, tg.name
+            "#
+        )?;
+        let tag_iter = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)?))
         })?;
 
-        let mut task_group_statistics = Vec::new();
-        for stat in group_iter {
-            task_group_statistics.push(stat?);
+        let mut tag_statistics = Vec::new();
+        for tag in tag_iter {
+            let (tag_id, tag_name, total_tasks) = tag?;
+            let tally = tag_tallies.remove(&tag_id).unwrap_or_default();
+            tag_statistics.push(TagStatistics {
+                tag_id,
+                tag_name,
+                total_tasks,
+                total_sessions: tally.total_sessions,
+                total_duration_minutes: (tally.total_duration_seconds / 60) as i32,
+                total_duration_seconds: tally.total_duration_seconds,
+                completed_sessions: tally.completed_sessions,
+                abandoned_sessions: tally.abandoned_sessions,
+                interrupted_sessions: tally.interrupted_sessions,
+                auto_closed_sessions: tally.auto_closed_sessions,
+                manual_sessions: tally.manual_sessions,
+                completion_rate: completion_rate(tally.completed_sessions, tally.total_sessions, tally.auto_closed_sessions),
+            });
         }
+        tag_statistics.sort_by_key(|stat| std::cmp::Reverse(stat.total_duration_seconds));
 
         Ok(StatisticsResponse {
             task_statistics,
             task_group_statistics,
+            tag_statistics,
+        })
+    }
+
+    pub fn get_time_series(&self, req: TimeSeriesRequest) -> AppResult<TimeSeriesResponse> {
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        let (range_start, range_end) = stats::parse_range(&req.start_date, &req.end_date, zone)?;
+        let include_manual = req.include_manual.unwrap_or(true);
+
+        let conn = self.conn.lock().unwrap();
+        let sessions = load_focus_sessions(&conn, include_manual, range_start, range_end)?;
+
+        // 先生成完整的时间桶，没有数据的桶保持为 0
+        let bucket_starts = stats::bucket_range(
+            zone.local_date(range_start),
+            zone.local_date(range_end - chrono::Duration::milliseconds(1)),
+            req.bucket,
+        );
+        let mut points: Vec<TimeSeriesPoint> = bucket_starts
+            .iter()
+            .map(|bucket_start| TimeSeriesPoint {
+                bucket_start: *bucket_start,
+                total_sessions: 0,
+                total_duration_minutes: 0,
+                total_duration_seconds: 0,
+                task_groups: Vec::new(),
+            })
+            .collect();
+        let mut groups: BTreeMap<String, (String, Option<String>)> = BTreeMap::new();
+        let mut group_totals: HashMap<(NaiveDate, String), (i32, i64)> = HashMap::new();
+
+        for session in sessions {
+            // 跨午夜的会话按时间比例拆分到各自的日期，会话数只计入开始所在的桶
+            let first_bucket = stats::bucket_start(zone.local_date(session.clipped.start), req.bucket);
+            for (date, seconds) in session.clipped.seconds_by_day(zone) {
+                let bucket_start = stats::bucket_start(date, req.bucket);
+                let Ok(index) = bucket_starts.binary_search(&bucket_start) else {
+                    continue;
+                };
+                let counted = i32::from(bucket_start == first_bucket);
+                points[index].total_sessions += counted;
+                points[index].total_duration_seconds += seconds;
+
+                let totals = group_totals
+                    .entry((bucket_start, session.task_group_id.clone()))
+                    .or_insert((0, 0));
+                totals.0 += counted;
+                totals.1 += seconds;
+            }
+            groups.insert(session.task_group_id, (session.task_group_name, session.task_group_color));
+        }
+
+        let by_task_group = req.by_task_group.unwrap_or(false);
+        for point in &mut points {
+            point.total_duration_minutes = (point.total_duration_seconds / 60) as i32;
+            if by_task_group {
+                // 每个桶都列出全部任务组，便于绘制堆叠图
+                point.task_groups = groups
+                    .iter()
+                    .map(|(group_id, (group_name, group_color))| {
+                        let (total_sessions, total_duration_seconds) = group_totals
+                            .get(&(point.bucket_start, group_id.clone()))
+                            .copied()
+                            .unwrap_or((0, 0));
+                        TaskGroupSeriesValue {
+                            task_group_id: group_id.clone(),
+                            task_group_name: group_name.clone(),
+                            task_group_color: group_color.clone(),
+                            total_sessions,
+                            total_duration_minutes: (total_duration_seconds / 60) as i32,
+                            total_duration_seconds,
+                        }
+                    })
+                    .collect();
+            }
+        }
+
+        Ok(TimeSeriesResponse {
+            bucket: req.bucket,
+            points,
+        })
+    }
+
+    pub fn get_task_effort(&self, task_id: &str) -> AppResult<TaskEffort> {
+        let conn = self.conn.lock().unwrap();
+        let task = find_task(&conn, task_id)?;
+        let (total_sessions, actual_seconds): (i32, i64) = conn.query_row(
+            r#"
+            SELECT COUNT(*), COALESCE(SUM(COALESCE(duration_seconds, 0)), 0)
+            FROM task_sessions
+            WHERE task_id = ? AND end_time IS NOT NULL AND kind = 'Focus'
+            "#,
+            [task_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+
+        let actual_minutes = (actual_seconds / 60) as i32;
+        Ok(TaskEffort {
+            task_id: task.id,
+            estimate_minutes: task.estimate_minutes,
+            total_sessions,
+            actual_minutes,
+            actual_seconds,
+            remaining_minutes: task.estimate_minutes.map(|estimate| estimate - actual_minutes),
+            ratio: task.estimate_minutes.map(|estimate| estimate_ratio(actual_seconds, estimate)),
         })
     }
 
-    pub fn get_tasks_by_group(&self, task_group_id: &str) -> SqliteResult<Vec<Task>> {
+    pub fn get_estimate_report(&self, req: EstimateReportRequest) -> AppResult<EstimateReport> {
+        let zone = stats::Zone::resolve(req.timezone.as_deref())?;
+        let (range_start, range_end) = stats::parse_range(&req.start_date, &req.end_date, zone)?;
+        let include_manual = req.include_manual.unwrap_or(true);
+
+        let conn = self.conn.lock().unwrap();
+        let mut period_seconds: HashMap<String, i64> = HashMap::new();
+        for session in load_focus_sessions(&conn, include_manual, range_start, range_end)? {
+            *period_seconds.entry(session.task_id).or_default() += session.clipped.duration_seconds;
+        }
+        let actual_seconds = load_actual_seconds(&conn, include_manual, range_end)?;
+
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT {}, tg.name
+            FROM tasks t
+            JOIN task_groups tg ON t.task_group_id = tg.id
+            WHERE t.estimate_minutes IS NOT NULL
+            "#,
+            prefixed("t", TASK_COLUMNS)
+        ))?;
+        let group_name_offset = column_count(TASK_COLUMNS);
+        let rows = stmt
+            .query_map([], |row| Ok((map_task(row, 0)?, row.get::<_, String>(group_name_offset)?)))?
+            .collect::<SqliteResult<Vec<_>>>()?;
+
+        let mut tasks = Vec::new();
+        let mut groups: BTreeMap<String, TaskGroupEstimateAccuracy> = BTreeMap::new();
+        for (task, task_group_name) in rows {
+            let Some(period) = period_seconds.get(&task.id).copied() else {
+                continue;
+            };
+            let estimate_minutes = task.estimate_minutes.unwrap_or_default();
+            let actual = actual_seconds.get(&task.id).copied().unwrap_or(period);
+            let actual_minutes = (actual / 60) as i32;
+
+            let group = groups.entry(task.task_group_id.clone()).or_insert_with(|| TaskGroupEstimateAccuracy {
+                task_group_id: task.task_group_id.clone(),
+                task_group_name: task_group_name.clone(),
+                estimated_tasks: 0,
+                over_estimate_tasks: 0,
+                under_estimate_tasks: 0,
+                estimate_minutes: 0,
+                actual_minutes: 0,
+                actual_seconds: 0,
+                ratio: 0.0,
+                over_ratio: 0.0,
+                under_ratio: 0.0,
+            });
+            group.estimated_tasks += 1;
+            group.estimate_minutes += estimate_minutes;
+            group.actual_seconds += actual;
+            match actual.cmp(&(estimate_minutes as i64 * 60)) {
+                std::cmp::Ordering::Greater => group.over_estimate_tasks += 1,
+                std::cmp::Ordering::Less => group.under_estimate_tasks += 1,
+                std::cmp::Ordering::Equal => {}
+            }
+
+            tasks.push(TaskEstimateAccuracy {
+                task_id: task.id,
+                task_name: task.name,
+                task_group_id: task.task_group_id,
+                task_group_name,
+                status: task.status,
+                estimate_minutes,
+                actual_minutes,
+                actual_seconds: actual,
+                period_seconds: period,
+                variance_minutes: actual_minutes - estimate_minutes,
+                ratio: estimate_ratio(actual, estimate_minutes),
+            });
+        }
+        // 偏差最大的任务排在前面
+        tasks.sort_by_key(|task| std::cmp::Reverse(task.variance_minutes.abs()));
+
+        let task_groups = groups
+            .into_values()
+            .map(|mut group| {
+                group.actual_minutes = (group.actual_seconds / 60) as i32;
+                group.ratio = estimate_ratio(group.actual_seconds, group.estimate_minutes);
+                group.over_ratio = group.over_estimate_tasks as f64 / group.estimated_tasks as f64;
+                group.under_ratio = group.under_estimate_tasks as f64 / group.estimated_tasks as f64;
+                group
+            })
+            .collect();
+
+        Ok(EstimateReport { tasks, task_groups })
+    }
+
+    // 回收站中的任务总是隐藏，已归档的任务默认隐藏
+    pub fn get_tasks_by_group(&self, task_group_id: &str, filter: TaskFilter) -> AppResult<Vec<Task>> {
+        let mut conditions = vec!["task_group_id = ?".to_string(), "deleted_at IS NULL".to_string()];
+        let mut params: Vec<SqlValue> = vec![task_group_id.to_string().into()];
+
+        if !filter.include_archived.unwrap_or(false) {
+            conditions.push("archived_at IS NULL".to_string());
+        }
+        if let Some(tag_id) = filter.tag_id {
+            conditions.push("id IN (SELECT task_id FROM task_tags WHERE tag_id = ?)".to_string());
+            params.push(tag_id.into());
+        }
+        if let Some(statuses) = filter.statuses.filter(|statuses| !statuses.is_empty()) {
+            conditions.push(format!("status IN ({})", vec!["?"; statuses.len()].join(", ")));
+            params.extend(statuses.iter().map(|status| SqlValue::from(status.as_str().to_string())));
+        }
+        if let Some(priority) = filter.priority {
+            conditions.push("priority = ?".to_string());
+            params.push(priority.as_str().to_string().into());
+        }
+
         let conn = self.conn.lock().unwrap();
         let mut stmt = conn.prepare(
-            "SELECT id, task_group_id, name, description, duration_minutes, created_at, updated_at FROM tasks WHERE task_group_id = ? ORDER BY created_at DESC"
+            &format!(
+                "SELECT {} FROM tasks WHERE {} ORDER BY {}",
+                TASK_COLUMNS,
+                conditions.join(" AND "),
+                task_order_by(filter.sort_by.unwrap_or(TaskSortBy::Manual))
+            )
         )?;
         
-        let task_iter = stmt.query_map([task_group_id], |row| {
-            Ok(Task {
-                id: row.get(0)?,
-                task_group_id: row.get(1)?,
-                name: row.get(2)?,
-                description: row.get(3)?,
-                duration_minutes: row.get::<_, Option<i32>>(4)?,
-                created_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(5)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-                updated_at: DateTime::parse_from_rfc3339(&row.get::<_, String>(6)?)
-                    .unwrap()
-                    .with_timezone(&Utc),
-            })
-        })?;
+        let task_iter = stmt.query_map(rusqlite::params_from_iter(params), |row| map_task(row, 0))?;
     
         let mut tasks = Vec::new();
         for task in task_iter {
@@ -581,3 +2915,617 @@ impl Database {
         Ok(tasks)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (Database, TaskGroup, Task) {
+        setup_at(":memory:")
+    }
+
+    fn setup_at(database_path: &str) -> (Database, TaskGroup, Task) {
+        let db = Database::new(database_path).unwrap();
+        let group = db
+            .create_task_group(CreateTaskGroupRequest {
+                name: "Work".to_string(),
+                description: Some("Deep work".to_string()),
+                parent_id: None,
+                color: None,
+                icon: None,
+                default_duration_minutes: None,
+                default_timer_mode: None,
+            })
+            .unwrap();
+        let task = db
+            .create_task(CreateTaskRequest {
+                task_group_id: group.id.clone(),
+                name: "Write".to_string(),
+                description: Some("Draft chapter".to_string()),
+                duration_minutes: Some(25),
+                timer_mode: None,
+                pomodoro: Some(PomodoroConfig {
+                    work_minutes: 25,
+                    short_break_minutes: 5,
+                    long_break_minutes: 15,
+                    long_break_every: 4,
+                }),
+                priority: None,
+                due_date: None,
+                estimate_minutes: None,
+                recurrence: None,
+            })
+            .unwrap();
+        (db, group, task)
+    }
+
+    #[test]
+    fn task_group_patch_absent_keeps_fields() {
+        let (db, group, _) = setup();
+        let updated = db.update_task_group(&group.id, UpdateTaskGroupRequest::default()).unwrap();
+        assert_eq!(updated.name, "Work");
+        assert_eq!(updated.description.as_deref(), Some("Deep work"));
+    }
+
+    #[test]
+    fn task_group_patch_value_sets_only_that_field() {
+        let (db, group, _) = setup();
+        let updated = db
+            .update_task_group(&group.id, UpdateTaskGroupRequest {
+                name: Patch::Value("Study".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.name, "Study");
+        assert_eq!(updated.description.as_deref(), Some("Deep work"));
+
+        let updated = db
+            .update_task_group(&group.id, UpdateTaskGroupRequest {
+                description: Patch::Value("Exams".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.name, "Study");
+        assert_eq!(updated.description.as_deref(), Some("Exams"));
+    }
+
+    #[test]
+    fn task_group_patch_null_clears_description_but_not_name() {
+        let (db, group, _) = setup();
+        let updated = db
+            .update_task_group(&group.id, UpdateTaskGroupRequest {
+                description: Patch::Null,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.name, "Work");
+        assert_eq!(updated.description, None);
+
+        let result = db.update_task_group(&group.id, UpdateTaskGroupRequest {
+            name: Patch::Null,
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn task_patch_absent_keeps_fields() {
+        let (db, _, task) = setup();
+        let updated = db.update_task(&task.id, UpdateTaskRequest::default()).unwrap();
+        assert_eq!(updated.name, "Write");
+        assert_eq!(updated.description.as_deref(), Some("Draft chapter"));
+        assert_eq!(updated.duration_minutes, Some(25));
+        assert_eq!(updated.pomodoro.map(|p| p.long_break_every), Some(4));
+    }
+
+    #[test]
+    fn task_patch_value_sets_only_that_field() {
+        let (db, _, task) = setup();
+        let updated = db
+            .update_task(&task.id, UpdateTaskRequest {
+                duration_minutes: Patch::Value(50),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.duration_minutes, Some(50));
+        assert_eq!(updated.name, "Write");
+        assert_eq!(updated.description.as_deref(), Some("Draft chapter"));
+
+        let updated = db
+            .update_task(&task.id, UpdateTaskRequest {
+                name: Patch::Value("Edit".to_string()),
+                description: Patch::Value("Second pass".to_string()),
+                pomodoro: Patch::Value(PomodoroConfig {
+                    work_minutes: 50,
+                    short_break_minutes: 10,
+                    long_break_minutes: 30,
+                    long_break_every: 2,
+                }),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.name, "Edit");
+        assert_eq!(updated.description.as_deref(), Some("Second pass"));
+        assert_eq!(updated.duration_minutes, Some(50));
+        assert_eq!(updated.pomodoro.map(|p| (p.work_minutes, p.long_break_every)), Some((50, 2)));
+    }
+
+    #[test]
+    fn task_patch_null_clears_field() {
+        let (db, _, task) = setup();
+        let updated = db
+            .update_task(&task.id, UpdateTaskRequest {
+                duration_minutes: Patch::Null,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.duration_minutes, None);
+        assert!(updated.pomodoro.is_some());
+
+        let updated = db
+            .update_task(&task.id, UpdateTaskRequest {
+                description: Patch::Null,
+                pomodoro: Patch::Null,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(updated.description, None);
+        assert!(updated.pomodoro.is_none());
+        assert_eq!(updated.name, "Write");
+
+        let result = db.update_task(&task.id, UpdateTaskRequest {
+            name: Patch::Null,
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn patch_of_missing_record_is_not_found() {
+        let (db, _, _) = setup();
+        assert!(matches!(
+            db.update_task("missing", UpdateTaskRequest::default()),
+            Err(AppError::NotFound(_))
+        ));
+        assert!(matches!(
+            db.update_task_group("missing", UpdateTaskGroupRequest::default()),
+            Err(AppError::NotFound(_))
+        ));
+    }
+
+    // 只给日期时，结束日期要包含当天全部会话
+    #[test]
+    fn list_sessions_includes_whole_end_day() {
+        let (db, _, task) = setup();
+        let session_at = |start: &str| {
+            let start_time = DateTime::parse_from_rfc3339(start).unwrap().with_timezone(&Utc);
+            db.create_manual_session(CreateManualSessionRequest {
+                task_id: task.id.clone(),
+                start_time,
+                end_time: start_time + chrono::Duration::minutes(20),
+                duration_minutes: None,
+                outcome: None,
+            })
+            .unwrap()
+        };
+        session_at("2025-03-10T09:00:00Z");
+        session_at("2025-03-11T00:30:00Z");
+
+        let count = |start_date: &str, end_date: Option<&str>, timezone: &str| {
+            db.list_sessions(ListSessionsRequest {
+                start_date: Some(start_date.to_string()),
+                end_date: end_date.map(String::from),
+                timezone: Some(timezone.to_string()),
+                ..Default::default()
+            })
+            .unwrap()
+            .sessions
+            .len()
+        };
+        assert_eq!(count("2025-03-10", Some("2025-03-10"), "UTC"), 1);
+        assert_eq!(count("2025-03-11", Some("2025-03-11"), "UTC"), 1);
+        // 纽约的 3 月 10 日对应 UTC 04:00 到次日 04:00
+        assert_eq!(count("2025-03-10", Some("2025-03-10"), "America/New_York"), 2);
+        assert_eq!(count("2025-03-10", None, "UTC"), 2);
+        assert!(matches!(
+            db.list_sessions(ListSessionsRequest {
+                start_date: Some("2025-03-11".to_string()),
+                end_date: Some("2025-03-10".to_string()),
+                ..Default::default()
+            }),
+            Err(AppError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn goal_is_met_once_per_period() {
+        let (db, group, task) = setup();
+        let goal = db
+            .create_goal(CreateGoalRequest {
+                name: "Deep work".to_string(),
+                scope: GoalScope::TaskGroup,
+                scope_id: group.id.clone(),
+                period: GoalPeriod::Day,
+                days_of_week: None,
+                target_minutes: 60,
+                timezone: Some("UTC".to_string()),
+            })
+            .unwrap();
+        let session_at = |start: &str, minutes: i64| {
+            let start_time = DateTime::parse_from_rfc3339(start).unwrap().with_timezone(&Utc);
+            db.create_manual_session(CreateManualSessionRequest {
+                task_id: task.id.clone(),
+                start_time,
+                end_time: start_time + chrono::Duration::minutes(minutes),
+                duration_minutes: None,
+                outcome: None,
+            })
+            .unwrap()
+            .id
+        };
+
+        let first = session_at("2025-03-10T09:00:00Z", 40);
+        assert!(db.goals_met_by(&first).unwrap().is_empty());
+
+        let second = session_at("2025-03-10T10:00:00Z", 30);
+        let met = db.goals_met_by(&second).unwrap();
+        assert_eq!(met.len(), 1);
+        assert_eq!(met[0].goal.id, goal.id);
+        assert_eq!(met[0].session_id, second);
+        assert_eq!(met[0].period.period_start, NaiveDate::from_ymd_opt(2025, 3, 10).unwrap());
+        assert_eq!(met[0].period.focused_minutes, 70);
+
+        // 同一天之后的会话不再重复触发，第二天重新计算
+        let third = session_at("2025-03-10T11:00:00Z", 30);
+        assert!(db.goals_met_by(&third).unwrap().is_empty());
+        let next_day = session_at("2025-03-11T09:00:00Z", 60);
+        assert_eq!(db.goals_met_by(&next_day).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn switched_session_counts_towards_goal() {
+        let (db, _, task) = setup();
+        db.create_goal(CreateGoalRequest {
+            name: "Warm up".to_string(),
+            scope: GoalScope::Task,
+            scope_id: task.id.clone(),
+            period: GoalPeriod::Week,
+            days_of_week: None,
+            target_minutes: 1,
+            timezone: None,
+        })
+        .unwrap();
+        let start = |on_active| {
+            db.start_session(StartSessionRequest {
+                task_id: task.id.clone(),
+                kind: Some(SessionKind::Focus),
+                on_active,
+            })
+            .unwrap()
+        };
+
+        let first = start(None);
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE task_sessions SET start_time = ? WHERE id = ?",
+                rusqlite::params![(Utc::now() - chrono::Duration::minutes(2)).to_rfc3339(), first.id],
+            )
+            .unwrap();
+        start(Some(ActiveSessionPolicy::Switch));
+
+        let met = db.goals_met_by(&first.id).unwrap();
+        assert_eq!(met.len(), 1);
+        let closed = find_session(&db.conn.lock().unwrap(), &first.id).unwrap();
+        assert_eq!(closed.outcome, Some(SessionOutcome::Interrupted));
+    }
+
+    fn insert_open_session(conn: &Connection, id: &str, task_id: &str, start_time: DateTime<Utc>, planned_minutes: Option<i32>) {
+        conn.execute(
+            r#"
+            INSERT INTO task_sessions (id, task_id, start_time, completed, kind, planned_minutes, created_at)
+            VALUES (?1, ?2, ?3, 0, 'Focus', ?4, ?3)
+            "#,
+            rusqlite::params![id, task_id, start_time.to_rfc3339(), planned_minutes],
+        )
+        .unwrap();
+    }
+
+    // 超过上限时长的未结束会话在启动时自动关闭；倒计时按计划时长封顶，正向计时按默认上限
+    #[test]
+    fn recovery_closes_open_session_past_its_cap() {
+        let path = std::env::temp_dir().join(format!("focus-recovery-{}.db", Uuid::new_v4()));
+        let path = path.to_string_lossy().to_string();
+        let now = Utc::now();
+        let reopen_with = |id: &str, start_time: DateTime<Utc>, planned_minutes: Option<i32>| {
+            let (db, _, task) = setup_at(&path);
+            insert_open_session(&db.conn.lock().unwrap(), id, &task.id, start_time, planned_minutes);
+            drop(db);
+            let db = Database::new(&path).unwrap();
+            let conn = db.conn.lock().unwrap();
+            find_session(&conn, id).unwrap()
+        };
+
+        let countdown = reopen_with("countdown", now - chrono::Duration::days(2), Some(25));
+        assert_eq!(countdown.outcome, Some(SessionOutcome::AutoClosed));
+        assert!(countdown.needs_review);
+        assert_eq!(countdown.duration_seconds, Some(25 * 60));
+        assert_eq!(countdown.end_time, Some(countdown.start_time + chrono::Duration::minutes(25)));
+
+        let forward = reopen_with("forward", now - chrono::Duration::hours(10), None);
+        assert_eq!(forward.outcome, Some(SessionOutcome::AutoClosed));
+        assert_eq!(forward.duration_seconds, Some(RECOVERED_SESSION_CAP_MINUTES * 60));
+        assert_eq!(
+            forward.end_time,
+            Some(forward.start_time + chrono::Duration::minutes(RECOVERED_SESSION_CAP_MINUTES))
+        );
+
+        let recent = reopen_with("recent", now - chrono::Duration::minutes(5), None);
+        assert_eq!((recent.end_time, recent.outcome, recent.needs_review), (None, None, false));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    fn insert_recovered_session(conn: &Connection, id: &str, task_id: &str, start_time: DateTime<Utc>, minutes: i64) {
+        conn.execute(
+            r#"
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, duration_seconds,
+                completed, outcome, kind, needs_review, created_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0, 'AutoClosed', 'Focus', 1, ?3)
+            "#,
+            rusqlite::params![
+                id,
+                task_id,
+                start_time.to_rfc3339(),
+                (start_time + chrono::Duration::minutes(minutes)).to_rfc3339(),
+                minutes,
+                minutes * 60
+            ],
+        )
+        .unwrap();
+    }
+
+    fn resolve(db: &Database, session_id: &str, duration_minutes: i32) -> AppResult<TaskSession> {
+        db.resolve_recovered_session(ResolveRecoveredSessionRequest {
+            session_id: session_id.to_string(),
+            outcome: Some(SessionOutcome::Completed),
+            duration_minutes: Some(duration_minutes),
+        })
+    }
+
+    #[test]
+    fn resolve_recovered_session_rejects_non_positive_or_future_duration() {
+        let (db, _, task) = setup();
+        let start = Utc::now() - chrono::Duration::hours(2);
+        insert_recovered_session(&db.conn.lock().unwrap(), "recovered", &task.id, start, 25);
+
+        for minutes in [-30, 0, 600] {
+            let result = resolve(&db, "recovered", minutes);
+            assert!(matches!(result, Err(AppError::Validation(_))), "{} minutes: {:?}", minutes, result);
+        }
+        let unchanged = find_session(&db.conn.lock().unwrap(), "recovered").unwrap();
+        assert_eq!((unchanged.duration_seconds, unchanged.needs_review), (Some(25 * 60), true));
+
+        let resolved = resolve(&db, "recovered", 90).unwrap();
+        assert_eq!(resolved.duration_seconds, Some(90 * 60));
+        assert_eq!(resolved.end_time, Some(start + chrono::Duration::minutes(90)));
+        assert!(!resolved.needs_review);
+    }
+
+    #[test]
+    fn resolve_recovered_session_rejects_overlap_with_next_session() {
+        let (db, _, task) = setup();
+        let start = Utc::now() - chrono::Duration::hours(3);
+        {
+            let conn = db.conn.lock().unwrap();
+            insert_recovered_session(&conn, "recovered", &task.id, start, 25);
+            insert_recovered_session(&conn, "next", &task.id, start + chrono::Duration::minutes(30), 25);
+        }
+
+        let result = resolve(&db, "recovered", 60);
+        assert!(matches!(result, Err(AppError::Conflict(_))), "{:?}", result);
+
+        let resolved = resolve(&db, "recovered", 30).unwrap();
+        assert_eq!(resolved.end_time, Some(start + chrono::Duration::minutes(30)));
+    }
+
+    #[test]
+    fn unknown_session_outcome_is_corruption() {
+        let (db, _, task) = setup();
+        let start = Utc::now() - chrono::Duration::hours(2);
+        {
+            let conn = db.conn.lock().unwrap();
+            insert_recovered_session(&conn, "bogus", &task.id, start, 25);
+            conn.execute("UPDATE task_sessions SET outcome = 'Bogus' WHERE id = 'bogus'", []).unwrap();
+        }
+
+        let listed = db.list_sessions(ListSessionsRequest::default());
+        assert!(matches!(listed, Err(AppError::Corruption(_))), "{:?}", listed);
+        let stats = db.get_statistics(StatisticsRequest {
+            start_date: start.date_naive().to_string(),
+            end_date: Utc::now().date_naive().to_string(),
+            group_by: StatisticsGroupBy::Task,
+            include_manual: None,
+            timezone: Some("UTC".to_string()),
+        });
+        assert!(matches!(stats, Err(AppError::Corruption(_))), "{:?}", stats);
+    }
+
+    #[test]
+    fn unknown_pomodoro_next_kind_is_corruption() {
+        let (db, _, task) = setup();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute(
+                "INSERT INTO pomodoro_state (task_id, completed_focus_sessions, next_kind, updated_at) VALUES (?, 1, 'Bogus', ?)",
+                rusqlite::params![task.id, Utc::now().to_rfc3339()],
+            )
+            .unwrap();
+
+        let state = db.get_pomodoro_state(&task.id);
+        assert!(matches!(state, Err(AppError::Corruption(_))), "{:?}", state);
+    }
+
+    #[test]
+    fn unknown_recurrence_rule_is_corruption() {
+        let (db, group, task) = setup();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE tasks SET recurrence_rule = 'Bogus' WHERE id = ?", [&task.id])
+            .unwrap();
+
+        let tasks = db.get_tasks_by_group(&group.id, TaskFilter::default());
+        assert!(matches!(tasks, Err(AppError::Corruption(_))), "{:?}", tasks);
+    }
+
+    #[test]
+    fn unknown_default_timer_mode_is_corruption() {
+        let (db, group, _) = setup();
+        db.conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE task_groups SET default_timer_mode = 'Bogus' WHERE id = ?", [&group.id])
+            .unwrap();
+
+        let groups = db.get_task_groups(false);
+        assert!(matches!(groups, Err(AppError::Corruption(_))), "{:?}", groups);
+    }
+
+    fn create_timed_task(db: &Database, group_id: &str, duration_minutes: Option<i32>, timer_mode: Option<TimerMode>) -> AppResult<Task> {
+        db.create_task(CreateTaskRequest {
+            task_group_id: group_id.to_string(),
+            name: "Timed".to_string(),
+            description: None,
+            duration_minutes,
+            timer_mode,
+            pomodoro: None,
+            priority: None,
+            due_date: None,
+            estimate_minutes: None,
+            recurrence: None,
+        })
+    }
+
+    #[test]
+    fn forward_task_with_duration_is_rejected() {
+        let (db, group, _) = setup();
+        let result = create_timed_task(&db, &group.id, Some(30), Some(TimerMode::Forward));
+        assert!(matches!(result, Err(AppError::Validation(_))), "{:?}", result);
+
+        let forward = create_timed_task(&db, &group.id, None, Some(TimerMode::Forward)).unwrap();
+        assert_eq!(forward.duration_minutes, None);
+    }
+
+    #[test]
+    fn countdown_group_default_needs_a_duration() {
+        let (db, group, _) = setup();
+        db.update_task_group(&group.id, UpdateTaskGroupRequest {
+            default_timer_mode: Patch::Value(TimerMode::Countdown),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let result = create_timed_task(&db, &group.id, None, None);
+        assert!(matches!(result, Err(AppError::Validation(_))), "{:?}", result);
+
+        let countdown = create_timed_task(&db, &group.id, Some(45), None).unwrap();
+        assert_eq!(countdown.duration_minutes, Some(45));
+        let forward = create_timed_task(&db, &group.id, None, Some(TimerMode::Forward)).unwrap();
+        assert_eq!(forward.duration_minutes, None);
+    }
+
+    // 解析不出任何一天的掩码会让历史周期的回溯永远找不到下一天
+    #[test]
+    fn invalid_goal_days_mask_is_corruption() {
+        let (db, group, _) = setup();
+        let goal = db
+            .create_goal(CreateGoalRequest {
+                name: "Weekdays".to_string(),
+                scope: GoalScope::TaskGroup,
+                scope_id: group.id.clone(),
+                period: GoalPeriod::Day,
+                days_of_week: Some(vec![Weekday::Mon, Weekday::Fri]),
+                target_minutes: 60,
+                timezone: Some("UTC".to_string()),
+            })
+            .unwrap();
+
+        for mask in [0, 1 << 7, -1] {
+            db.conn
+                .lock()
+                .unwrap()
+                .execute("UPDATE goals SET days_of_week = ? WHERE id = ?", rusqlite::params![mask, goal.id])
+                .unwrap();
+            let progress = db.get_goal_progress(GoalProgressRequest {
+                goal_id: Some(goal.id.clone()),
+                history_periods: None,
+                include_manual: None,
+            });
+            assert!(matches!(progress, Err(AppError::Corruption(_))), "mask {}: {:?}", mask, progress);
+        }
+    }
+
+    #[test]
+    fn end_session_override_cannot_exceed_focused_time() {
+        let (db, _, task) = setup();
+        let session = db
+            .start_session(StartSessionRequest {
+                task_id: task.id.clone(),
+                kind: Some(SessionKind::Focus),
+                on_active: None,
+            })
+            .unwrap();
+        {
+            let conn = db.conn.lock().unwrap();
+            // 开始于 30 分钟前，其中暂停了 10 分钟
+            conn.execute(
+                "UPDATE task_sessions SET start_time = ?, total_paused_duration_ms = ? WHERE id = ?",
+                rusqlite::params![(Utc::now() - chrono::Duration::minutes(30)).to_rfc3339(), 10 * 60_000, session.id],
+            )
+            .unwrap();
+        }
+        let end = |duration_minutes| {
+            db.end_session(EndSessionRequest {
+                session_id: session.id.clone(),
+                duration_minutes: Some(duration_minutes),
+                outcome: None,
+            })
+        };
+
+        let result = end(21);
+        assert!(matches!(result, Err(AppError::Validation(_))), "{:?}", result);
+        let ended = end(15).unwrap();
+        assert_eq!(ended.duration_seconds, Some(15 * 60));
+    }
+
+    #[test]
+    fn trashed_items_cannot_gain_tasks_or_sessions() {
+        let (db, group, task) = setup();
+        db.trash_task(&task.id).unwrap();
+        let end_time = Utc::now() - chrono::Duration::hours(1);
+        let manual = db.create_manual_session(CreateManualSessionRequest {
+            task_id: task.id.clone(),
+            start_time: end_time - chrono::Duration::minutes(25),
+            end_time,
+            duration_minutes: None,
+            outcome: None,
+        });
+        assert!(matches!(manual, Err(AppError::Conflict(_))), "{:?}", manual);
+
+        let other = create_timed_task(&db, &group.id, None, None).unwrap();
+        db.trash_task_group(&group.id).unwrap();
+        let created = create_timed_task(&db, &group.id, None, None);
+        assert!(matches!(created, Err(AppError::Conflict(_))), "{:?}", created);
+        let manual = db.create_manual_session(CreateManualSessionRequest {
+            task_id: other.id.clone(),
+            start_time: end_time - chrono::Duration::minutes(25),
+            end_time,
+            duration_minutes: None,
+            outcome: None,
+        });
+        assert!(matches!(manual, Err(AppError::Conflict(_))), "{:?}", manual);
+    }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+use rusqlite::ErrorCode;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::json;
+use crate::models::ActiveSession;
+
+#[derive(Debug)]
+pub enum AppError {
+    Database(rusqlite::Error),
+    NotFound(String),
+    Validation(String),
+    Conflict(String),
+    SessionAlreadyActive(Box<ActiveSession>),
+    Migration {
+        version: u32,
+        description: &'static str,
+        source: rusqlite::Error,
+    },
+    Corruption(String), // Stored data that can no longer be read back
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// Stable code the frontend can branch on.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) | AppError::Migration { .. } => "Database",
+            AppError::NotFound(_) => "NotFound",
+            AppError::Validation(_) => "Validation",
+            AppError::Conflict(_) | AppError::SessionAlreadyActive(_) => "Conflict",
+            AppError::Corruption(_) => "Corruption",
+        }
+    }
+
+    fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            // 区分“数据库被锁定”等 SQLite 层面的错误
+            AppError::Database(rusqlite::Error::SqliteFailure(e, _)) => Some(json!({
+                "sqlite_code": format!("{:?}", e.code),
+                "extended_code": e.extended_code,
+            })),
+            AppError::SessionAlreadyActive(active) => Some(json!({ "active_session": active })),
+            AppError::Migration { version, description, .. } => Some(json!({
+                "migration_version": version,
+                "migration": description,
+            })),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+            AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
+            AppError::Validation(msg) => write!(f, "Invalid input: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::SessionAlreadyActive(active) => write!(
+                f,
+                "Conflict: session {} for task \"{}\" is already active",
+                active.session.id, active.task.name
+            ),
+            AppError::Migration { version, description, source } => write!(
+                f,
+                "Migration {} ({}) failed: {}",
+                version, description, source
+            ),
+            AppError::Corruption(msg) => write!(f, "Corrupted data: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            // 行数据无法转换为模型，说明存储的数据已损坏
+            rusqlite::Error::FromSqlConversionFailure(..)
+            | rusqlite::Error::InvalidColumnType(..)
+            | rusqlite::Error::IntegralValueOutOfRange(..) => AppError::Corruption(e.to_string()),
+            rusqlite::Error::SqliteFailure(ref failure, _)
+                if matches!(failure.code, ErrorCode::DatabaseCorrupt | ErrorCode::NotADatabase) =>
+            {
+                AppError::Corruption(e.to_string())
+            }
+            e => AppError::Database(e),
+        }
+    }
+}
+
+/// Sent to the frontend as `{ code, message, details }`.
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
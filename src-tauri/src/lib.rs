@@ -1,31 +1,132 @@
 use std::sync::Arc;
-use tauri::{State, Manager};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, State, Manager};
 use std::sync::Mutex;
+use chrono::{NaiveDate, Utc};
 
 mod models;
 mod database;
+mod error;
+mod migrations;
+mod recurrence;
+mod stats;
+mod timer;
 
 use models::*;
 use database::Database;
+use error::AppError;
+use timer::{TimerEvent, TimerService};
 
 type DbState = Arc<Mutex<Database>>;
+type TimerState = Arc<Mutex<TimerService>>;
+
+fn sync_timer(db: &Database, timer: &Mutex<TimerService>) {
+    if let Err(e) = timer.lock().unwrap().sync(db) {
+        eprintln!("Failed to sync timer with database: {}", e);
+    }
+}
+
+fn spawn_timer_loop(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(Duration::from_secs(1));
+
+        // 与命令保持相同的加锁顺序：先数据库，后计时器
+        let (event, met) = {
+            let db_state = app.state::<DbState>();
+            let timer_state = app.state::<TimerState>();
+            let db = db_state.lock().unwrap();
+            let mut timer = timer_state.lock().unwrap();
+            let event = timer.tick(&db, Utc::now());
+            let met = match &event {
+                Ok(Some(TimerEvent::Finished(session))) => goals_met(&db, &session.id),
+                _ => Vec::new(),
+            };
+            (event, met)
+        };
+
+        let emitted = match event {
+            Ok(Some(TimerEvent::Tick(snapshot))) => app.emit(timer::TICK_EVENT, snapshot),
+            Ok(Some(TimerEvent::Finished(session))) => app.emit(timer::FINISHED_EVENT, session),
+            Ok(None) => Ok(()),
+            Err(e) => {
+                eprintln!("Timer tick failed: {}", e);
+                Ok(())
+            }
+        };
+        if let Err(e) = emitted {
+            eprintln!("Failed to emit timer event: {}", e);
+        }
+        emit_goals_met(&app, met);
+    });
+}
+
+pub const GOAL_MET_EVENT: &str = "goals://met";
+
+// 目标检查失败只记录日志，不影响会话本身
+fn goals_met(db: &Database, session_id: &str) -> Vec<GoalMet> {
+    db.goals_met_by(session_id).unwrap_or_else(|e| {
+        eprintln!("Failed to check goals: {}", e);
+        Vec::new()
+    })
+}
+
+fn emit_goals_met(app: &AppHandle, met: Vec<GoalMet>) {
+    for goal in met {
+        if let Err(e) = app.emit(GOAL_MET_EVENT, goal) {
+            eprintln!("Failed to emit goal event: {}", e);
+        }
+    }
+}
+
+pub const ROUTINES_RESET_EVENT: &str = "routines://reset";
+
+// 每分钟检查一次本地日期，跨天后重置当天的重复任务
+fn spawn_routine_scheduler(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_run: Option<NaiveDate> = None;
+        loop {
+            let zone = stats::Zone::Local;
+            let today = zone.local_date(Utc::now());
+            if last_run != Some(today) {
+                let reset = {
+                    let db_state = app.state::<DbState>();
+                    let db = db_state.lock().unwrap();
+                    db.run_routines(zone, today)
+                };
+                match reset {
+                    Ok(tasks) => {
+                        last_run = Some(today);
+                        if !tasks.is_empty() {
+                            if let Err(e) = app.emit(ROUTINES_RESET_EVENT, tasks) {
+                                eprintln!("Failed to emit routines event: {}", e);
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("Routine scheduler failed: {}", e),
+                }
+            }
+            std::thread::sleep(Duration::from_secs(60));
+        }
+    });
+}
 
 // Task Group Commands
 #[tauri::command]
 fn create_task_group(
     db: State<'_, DbState>,
     request: CreateTaskGroupRequest,
-) -> Result<TaskGroup, String> {
+) -> Result<TaskGroup, AppError> {
     let db = db.lock().unwrap();
     db.create_task_group(request)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn get_task_groups(db: State<'_, DbState>) -> Result<Vec<TaskGroup>, String> {
+fn get_task_groups(
+    db: State<'_, DbState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<TaskGroup>, AppError> {
     let db = db.lock().unwrap();
-    db.get_task_groups()
-        .map_err(|e| e.to_string())
+    db.get_task_groups(include_archived.unwrap_or(false))
 }
 
 #[tauri::command]
@@ -33,17 +134,57 @@ fn update_task_group(
     db: State<'_, DbState>,
     id: String,
     request: UpdateTaskGroupRequest,
-) -> Result<TaskGroup, String> {
+) -> Result<TaskGroup, AppError> {
     let db = db.lock().unwrap();
     db.update_task_group(&id, request)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn delete_task_group(db: State<'_, DbState>, id: String) -> Result<(), String> {
+fn get_task_group_tree(
+    db: State<'_, DbState>,
+    include_archived: Option<bool>,
+) -> Result<Vec<TaskGroupNode>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_task_group_tree(include_archived.unwrap_or(false))
+}
+
+#[tauri::command]
+fn reorder_task_groups(
+    db: State<'_, DbState>,
+    request: ReorderTaskGroupsRequest,
+) -> Result<Vec<TaskGroup>, AppError> {
+    let db = db.lock().unwrap();
+    db.reorder_task_groups(request)
+}
+
+#[tauri::command]
+fn archive_task_group(db: State<'_, DbState>, id: String) -> Result<TaskGroup, AppError> {
+    let db = db.lock().unwrap();
+    db.archive_task_group(&id)
+}
+
+#[tauri::command]
+fn unarchive_task_group(db: State<'_, DbState>, id: String) -> Result<TaskGroup, AppError> {
+    let db = db.lock().unwrap();
+    db.unarchive_task_group(&id)
+}
+
+#[tauri::command]
+fn trash_task_group(db: State<'_, DbState>, id: String) -> Result<TaskGroup, AppError> {
+    let db = db.lock().unwrap();
+    db.trash_task_group(&id)
+}
+
+#[tauri::command]
+fn restore_task_group(db: State<'_, DbState>, id: String) -> Result<TaskGroup, AppError> {
     let db = db.lock().unwrap();
-    db.delete_task_group(&id)
-        .map_err(|e| e.to_string())
+    db.restore_task_group(&id)
+}
+
+#[tauri::command]
+fn purge_task_group(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.purge_task_group(&id)
 }
 
 // Task Commands
@@ -51,20 +192,55 @@ fn delete_task_group(db: State<'_, DbState>, id: String) -> Result<(), String> {
 fn create_task(
     db: State<'_, DbState>,
     request: CreateTaskRequest,
-) -> Result<Task, String> {
+) -> Result<Task, AppError> {
     let db = db.lock().unwrap();
     db.create_task(request)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 fn get_tasks_by_group(
     db: State<'_, DbState>,
     task_group_id: String,
-) -> Result<Vec<Task>, String> {
+    filter: Option<TaskFilter>,
+) -> Result<Vec<Task>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_tasks_by_group(&task_group_id, filter.unwrap_or_default())
+}
+
+#[tauri::command]
+fn reorder_tasks(
+    db: State<'_, DbState>,
+    request: ReorderTasksRequest,
+) -> Result<Vec<Task>, AppError> {
+    let db = db.lock().unwrap();
+    db.reorder_tasks(request)
+}
+
+#[tauri::command]
+fn set_task_status(
+    db: State<'_, DbState>,
+    request: SetTaskStatusRequest,
+) -> Result<Task, AppError> {
+    let db = db.lock().unwrap();
+    db.set_task_status(request)
+}
+
+#[tauri::command]
+fn get_due_routines(
+    db: State<'_, DbState>,
+    request: DueTasksRequest,
+) -> Result<DueRoutines, AppError> {
     let db = db.lock().unwrap();
-    db.get_tasks_by_group(&task_group_id)
-        .map_err(|e| e.to_string())
+    db.get_due_routines(request)
+}
+
+#[tauri::command]
+fn get_due_tasks(
+    db: State<'_, DbState>,
+    request: DueTasksRequest,
+) -> Result<DueTasks, AppError> {
+    let db = db.lock().unwrap();
+    db.get_due_tasks(request)
 }
 
 #[tauri::command]
@@ -72,45 +248,306 @@ fn update_task(
     db: State<'_, DbState>,
     id: String,
     request: UpdateTaskRequest,
-) -> Result<Task, String> {
+) -> Result<Task, AppError> {
     let db = db.lock().unwrap();
     db.update_task(&id, request)
-        .map_err(|e| e.to_string())
 }
 
 #[tauri::command]
-fn delete_task(db: State<'_, DbState>, id: String) -> Result<(), String> {
+fn archive_task(db: State<'_, DbState>, id: String) -> Result<Task, AppError> {
+    let db = db.lock().unwrap();
+    db.archive_task(&id)
+}
+
+#[tauri::command]
+fn unarchive_task(db: State<'_, DbState>, id: String) -> Result<Task, AppError> {
+    let db = db.lock().unwrap();
+    db.unarchive_task(&id)
+}
+
+#[tauri::command]
+fn trash_task(db: State<'_, DbState>, id: String) -> Result<Task, AppError> {
+    let db = db.lock().unwrap();
+    db.trash_task(&id)
+}
+
+#[tauri::command]
+fn restore_task(db: State<'_, DbState>, id: String) -> Result<Task, AppError> {
+    let db = db.lock().unwrap();
+    db.restore_task(&id)
+}
+
+#[tauri::command]
+fn purge_task(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.purge_task(&id)
+}
+
+#[tauri::command]
+fn get_trash(db: State<'_, DbState>) -> Result<Trash, AppError> {
+    let db = db.lock().unwrap();
+    db.get_trash()
+}
+
+// Tag Commands
+#[tauri::command]
+fn create_tag(
+    db: State<'_, DbState>,
+    request: CreateTagRequest,
+) -> Result<Tag, AppError> {
+    let db = db.lock().unwrap();
+    db.create_tag(request)
+}
+
+#[tauri::command]
+fn get_tags(db: State<'_, DbState>) -> Result<Vec<Tag>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_tags()
+}
+
+#[tauri::command]
+fn update_tag(
+    db: State<'_, DbState>,
+    id: String,
+    request: UpdateTagRequest,
+) -> Result<Tag, AppError> {
+    let db = db.lock().unwrap();
+    db.update_tag(&id, request)
+}
+
+#[tauri::command]
+fn delete_tag(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.delete_tag(&id)
+}
+
+#[tauri::command]
+fn get_task_tags(db: State<'_, DbState>, task_id: String) -> Result<Vec<Tag>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_task_tags(&task_id)
+}
+
+#[tauri::command]
+fn set_task_tags(
+    db: State<'_, DbState>,
+    request: SetTaskTagsRequest,
+) -> Result<Vec<Tag>, AppError> {
     let db = db.lock().unwrap();
-    db.delete_task(&id)
-        .map_err(|e| e.to_string())
+    db.set_task_tags(request)
+}
+
+// Subtask Commands
+#[tauri::command]
+fn get_subtasks(db: State<'_, DbState>, task_id: String) -> Result<Vec<Subtask>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_subtasks(&task_id)
+}
+
+#[tauri::command]
+fn add_subtask(
+    db: State<'_, DbState>,
+    request: AddSubtaskRequest,
+) -> Result<Subtask, AppError> {
+    let db = db.lock().unwrap();
+    db.add_subtask(request)
+}
+
+#[tauri::command]
+fn reorder_subtasks(
+    db: State<'_, DbState>,
+    request: ReorderSubtasksRequest,
+) -> Result<Vec<Subtask>, AppError> {
+    let db = db.lock().unwrap();
+    db.reorder_subtasks(request)
+}
+
+#[tauri::command]
+fn toggle_subtask(db: State<'_, DbState>, id: String) -> Result<Subtask, AppError> {
+    let db = db.lock().unwrap();
+    db.toggle_subtask(&id)
+}
+
+#[tauri::command]
+fn delete_subtask(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.delete_subtask(&id)
+}
+
+// Goal Commands
+#[tauri::command]
+fn create_goal(
+    db: State<'_, DbState>,
+    request: CreateGoalRequest,
+) -> Result<Goal, AppError> {
+    let db = db.lock().unwrap();
+    db.create_goal(request)
+}
+
+#[tauri::command]
+fn get_goals(db: State<'_, DbState>) -> Result<Vec<Goal>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_goals()
+}
+
+#[tauri::command]
+fn update_goal(
+    db: State<'_, DbState>,
+    id: String,
+    request: UpdateGoalRequest,
+) -> Result<Goal, AppError> {
+    let db = db.lock().unwrap();
+    db.update_goal(&id, request)
+}
+
+#[tauri::command]
+fn delete_goal(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.delete_goal(&id)
+}
+
+#[tauri::command]
+fn get_goal_progress(
+    db: State<'_, DbState>,
+    request: GoalProgressRequest,
+) -> Result<Vec<GoalProgress>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_goal_progress(request)
 }
 
 // Session Commands
 #[tauri::command]
 fn start_session(
+    app: AppHandle,
     db: State<'_, DbState>,
+    timer: State<'_, TimerState>,
     request: StartSessionRequest,
-) -> Result<TaskSession, String> {
+) -> Result<TaskSession, AppError> {
     let db = db.lock().unwrap();
-    db.start_session(request)
-        .map_err(|e| e.to_string())
+    // 开始成功时原来的会话已被切换中断，它计入的专注时间同样可能达成目标
+    let switched = db.get_active_session()?.map(|active| active.session.id);
+    let session = db.start_session(request)?;
+    sync_timer(&db, &timer);
+    if let Some(switched) = switched {
+        emit_goals_met(&app, goals_met(&db, &switched));
+    }
+    Ok(session)
 }
 
 #[tauri::command]
 fn end_session(
+    app: AppHandle,
     db: State<'_, DbState>,
+    timer: State<'_, TimerState>,
     request: EndSessionRequest,
-) -> Result<TaskSession, String> {
+) -> Result<TaskSession, AppError> {
+    let db = db.lock().unwrap();
+    let session = db.end_session(request)?;
+    sync_timer(&db, &timer);
+    emit_goals_met(&app, goals_met(&db, &session.id));
+    Ok(session)
+}
+
+#[tauri::command]
+fn pause_session(
+    db: State<'_, DbState>,
+    timer: State<'_, TimerState>,
+    request: PauseSessionRequest,
+) -> Result<TaskSession, AppError> {
+    let db = db.lock().unwrap();
+    let session = db.pause_session(request)?;
+    sync_timer(&db, &timer);
+    Ok(session)
+}
+
+#[tauri::command]
+fn resume_session(
+    db: State<'_, DbState>,
+    timer: State<'_, TimerState>,
+    request: ResumeSessionRequest,
+) -> Result<TaskSession, AppError> {
     let db = db.lock().unwrap();
-    db.end_session(request)
-        .map_err(|e| e.to_string())
+    let session = db.resume_session(request)?;
+    sync_timer(&db, &timer);
+    Ok(session)
 }
 
 #[tauri::command]
-fn get_active_session(db: State<'_, DbState>) -> Result<Option<ActiveSession>, String> {
+fn get_active_session(db: State<'_, DbState>) -> Result<Option<ActiveSession>, AppError> {
     let db = db.lock().unwrap();
     db.get_active_session()
-        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn create_manual_session(
+    db: State<'_, DbState>,
+    request: CreateManualSessionRequest,
+) -> Result<TaskSession, AppError> {
+    let db = db.lock().unwrap();
+    db.create_manual_session(request)
+}
+
+#[tauri::command]
+fn update_session(
+    db: State<'_, DbState>,
+    id: String,
+    request: UpdateSessionRequest,
+) -> Result<TaskSession, AppError> {
+    let db = db.lock().unwrap();
+    db.update_session(&id, request)
+}
+
+#[tauri::command]
+fn delete_session(db: State<'_, DbState>, id: String) -> Result<(), AppError> {
+    let db = db.lock().unwrap();
+    db.delete_session(&id)
+}
+
+#[tauri::command]
+fn list_sessions(
+    db: State<'_, DbState>,
+    request: ListSessionsRequest,
+) -> Result<SessionPage, AppError> {
+    let db = db.lock().unwrap();
+    db.list_sessions(request)
+}
+
+#[tauri::command]
+fn get_recovered_sessions(db: State<'_, DbState>) -> Result<Vec<SessionDetail>, AppError> {
+    let db = db.lock().unwrap();
+    db.get_recovered_sessions()
+}
+
+#[tauri::command]
+fn resolve_recovered_session(
+    db: State<'_, DbState>,
+    request: ResolveRecoveredSessionRequest,
+) -> Result<TaskSession, AppError> {
+    let db = db.lock().unwrap();
+    db.resolve_recovered_session(request)
+}
+
+#[tauri::command]
+fn get_pomodoro_state(
+    db: State<'_, DbState>,
+    task_id: String,
+) -> Result<PomodoroState, AppError> {
+    let db = db.lock().unwrap();
+    db.get_pomodoro_state(&task_id)
+}
+
+#[tauri::command]
+fn reset_pomodoro_cycle(
+    db: State<'_, DbState>,
+    task_id: String,
+) -> Result<PomodoroState, AppError> {
+    let db = db.lock().unwrap();
+    db.reset_pomodoro_cycle(&task_id)
+}
+
+#[tauri::command]
+fn get_timer_snapshot(timer: State<'_, TimerState>) -> Option<TimerSnapshot> {
+    let timer = timer.lock().unwrap();
+    timer.snapshot(Utc::now())
 }
 
 // Statistics Commands
@@ -118,10 +555,33 @@ fn get_active_session(db: State<'_, DbState>) -> Result<Option<ActiveSession>, S
 fn get_statistics(
     db: State<'_, DbState>,
     request: StatisticsRequest,
-) -> Result<StatisticsResponse, String> {
+) -> Result<StatisticsResponse, AppError> {
     let db = db.lock().unwrap();
     db.get_statistics(request)
-        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_time_series(
+    db: State<'_, DbState>,
+    request: TimeSeriesRequest,
+) -> Result<TimeSeriesResponse, AppError> {
+    let db = db.lock().unwrap();
+    db.get_time_series(request)
+}
+
+#[tauri::command]
+fn get_task_effort(db: State<'_, DbState>, task_id: String) -> Result<TaskEffort, AppError> {
+    let db = db.lock().unwrap();
+    db.get_task_effort(&task_id)
+}
+
+#[tauri::command]
+fn get_estimate_report(
+    db: State<'_, DbState>,
+    request: EstimateReportRequest,
+) -> Result<EstimateReport, AppError> {
+    let db = db.lock().unwrap();
+    db.get_estimate_report(request)
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -153,24 +613,75 @@ pub fn run() {
             
             let db = Database::new(&database_path.to_string_lossy())
                 .expect("Failed to initialize database");
+            let timer_state: TimerState = Arc::new(Mutex::new(TimerService::default()));
+            // 恢复重启前仍在进行的会话，倒计时在没有窗口时也会继续
+            sync_timer(&db, &timer_state);
             let db_state: DbState = Arc::new(Mutex::new(db));
-            
+
             app.manage(db_state);
+            app.manage(timer_state);
+            spawn_timer_loop(handle.clone());
+            spawn_routine_scheduler(handle.clone());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             create_task_group,
             get_task_groups,
             update_task_group,
-            delete_task_group,
+            get_task_group_tree,
+            reorder_task_groups,
+            archive_task_group,
+            unarchive_task_group,
+            trash_task_group,
+            restore_task_group,
+            purge_task_group,
             create_task,
             get_tasks_by_group,
             update_task,
-            delete_task,
+            archive_task,
+            unarchive_task,
+            trash_task,
+            restore_task,
+            purge_task,
+            get_trash,
+            reorder_tasks,
+            set_task_status,
+            get_due_tasks,
+            get_due_routines,
+            create_tag,
+            get_tags,
+            update_tag,
+            delete_tag,
+            get_task_tags,
+            set_task_tags,
+            get_subtasks,
+            add_subtask,
+            reorder_subtasks,
+            toggle_subtask,
+            delete_subtask,
+            create_goal,
+            get_goals,
+            update_goal,
+            delete_goal,
+            get_goal_progress,
             start_session,
             end_session,
+            pause_session,
+            resume_session,
             get_active_session,
-            get_statistics
+            get_timer_snapshot,
+            create_manual_session,
+            update_session,
+            delete_session,
+            list_sessions,
+            get_recovered_sessions,
+            resolve_recovered_session,
+            get_pomodoro_state,
+            reset_pomodoro_cycle,
+            get_statistics,
+            get_time_series,
+            get_task_effort,
+            get_estimate_report
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
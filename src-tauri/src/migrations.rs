@@ -0,0 +1,665 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, Result as SqliteResult};
+use crate::database::RECOVERED_SESSION_CAP_MINUTES;
+use crate::error::{AppError, AppResult};
+
+/// A single schema change. Migrations run in order, each once, inside its own
+/// transaction; the database's `PRAGMA user_version` records the last one applied.
+pub struct Migration {
+    pub version: u32,
+    pub description: &'static str,
+    pub up: fn(&Connection) -> SqliteResult<()>,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "base schema", up: base_schema },
+    Migration { version: 2, description: "session duration in seconds", up: session_duration_seconds },
+    Migration { version: 3, description: "session outcomes", up: session_outcomes },
+    Migration { version: 4, description: "session kind and planned minutes", up: session_kind },
+    Migration { version: 5, description: "pomodoro settings", up: pomodoro },
+    Migration { version: 6, description: "session review and manual flags", up: session_flags },
+    Migration { version: 7, description: "archive and trash", up: archive_and_trash },
+    Migration { version: 8, description: "tags", up: tags },
+    Migration { version: 9, description: "subtasks", up: subtasks },
+    Migration { version: 10, description: "task status", up: task_status },
+    Migration { version: 11, description: "task estimates", up: task_estimates },
+    Migration { version: 12, description: "recurrence", up: recurrence },
+    Migration { version: 13, description: "sort order", up: sort_order },
+    Migration { version: 14, description: "nested task groups", up: nested_task_groups },
+    Migration { version: 15, description: "task group appearance", up: task_group_appearance },
+    Migration { version: 16, description: "goals", up: goals },
+    Migration { version: 17, description: "goal timezone", up: goal_timezone },
+    Migration { version: 18, description: "single active session", up: single_active_session },
+];
+
+pub fn run(conn: &mut Connection) -> AppResult<()> {
+    run_migrations(conn, MIGRATIONS)
+}
+
+fn run_migrations(conn: &mut Connection, migrations: &[Migration]) -> AppResult<()> {
+    let current = schema_version(conn)?;
+    let latest = migrations.last().map_or(0, |migration| migration.version);
+    if current > latest {
+        return Err(AppError::Conflict(format!(
+            "database schema version {} is newer than this app supports ({})",
+            current, latest
+        )));
+    }
+
+    for migration in migrations.iter().filter(|migration| migration.version > current) {
+        let failed = |source| AppError::Migration {
+            version: migration.version,
+            description: migration.description,
+            source,
+        };
+        let tx = conn.transaction().map_err(failed)?;
+        (migration.up)(&tx).map_err(failed)?;
+        tx.pragma_update(None, "user_version", migration.version).map_err(failed)?;
+        tx.commit().map_err(failed)?;
+        println!("Applied migration {}: {}", migration.version, migration.description);
+    }
+
+    Ok(())
+}
+
+pub fn schema_version(conn: &Connection) -> SqliteResult<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> SqliteResult<bool> {
+    conn.query_row(
+        &format!("SELECT COUNT(*) FROM pragma_table_info('{}') WHERE name = ?", table),
+        [column],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|count| count > 0)
+}
+
+// 版本化之前的数据库可能已经有部分列，已存在时跳过；返回是否新增了该列
+fn add_column(conn: &Connection, table: &str, column: &str, definition: &str) -> SqliteResult<bool> {
+    if has_column(conn, table, column)? {
+        return Ok(false);
+    }
+    conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    Ok(true)
+}
+
+// v0.4 的表结构；已有数据库中这些表已存在
+fn base_schema(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS task_groups (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS tasks (
+            id TEXT PRIMARY KEY,
+            task_group_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            duration_minutes INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (task_group_id) REFERENCES task_groups (id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS task_sessions (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT,
+            duration_minutes INTEGER,
+            completed BOOLEAN NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+        );
+        "#,
+    )?;
+
+    add_column(conn, "task_sessions", "is_paused", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column(conn, "task_sessions", "paused_at", "TEXT")?;
+    add_column(conn, "task_sessions", "total_paused_duration_ms", "INTEGER NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+fn session_duration_seconds(conn: &Connection) -> SqliteResult<()> {
+    if add_column(conn, "task_sessions", "duration_seconds", "INTEGER")? {
+        // 旧会话只有分钟数，按分钟回填；没有分钟数的已结束会话按起止时间减去暂停时长计算
+        conn.execute(
+            "UPDATE task_sessions SET duration_seconds = duration_minutes * 60 WHERE duration_minutes IS NOT NULL",
+            [],
+        )?;
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET duration_seconds = MAX(
+                CAST(ROUND((julianday(end_time) - julianday(start_time)) * 86400) AS INTEGER)
+                    - COALESCE(total_paused_duration_ms, 0) / 1000,
+                0
+            )
+            WHERE duration_minutes IS NULL AND end_time IS NOT NULL
+            "#,
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn session_outcomes(conn: &Connection) -> SqliteResult<()> {
+    if add_column(conn, "task_sessions", "outcome", "TEXT")? {
+        // 旧会话没有结果记录：倒计时任务未达到目标时长的视为放弃
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET outcome = CASE
+                WHEN EXISTS (
+                    SELECT 1 FROM tasks t
+                    WHERE t.id = task_sessions.task_id
+                      AND t.duration_minutes IS NOT NULL
+                      AND COALESCE(task_sessions.duration_minutes, 0) < t.duration_minutes
+                ) THEN 'Abandoned'
+                ELSE 'Completed'
+            END
+            WHERE end_time IS NOT NULL
+            "#,
+            [],
+        )?;
+        conn.execute(
+            "UPDATE task_sessions SET completed = (outcome = 'Completed') WHERE end_time IS NOT NULL",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn session_kind(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "task_sessions", "kind", "TEXT NOT NULL DEFAULT 'Focus'")?;
+    if add_column(conn, "task_sessions", "planned_minutes", "INTEGER")? {
+        conn.execute(
+            "UPDATE task_sessions SET planned_minutes = (SELECT duration_minutes FROM tasks WHERE tasks.id = task_sessions.task_id)",
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn pomodoro(conn: &Connection) -> SqliteResult<()> {
+    for column in [
+        "pomodoro_work_minutes",
+        "pomodoro_short_break_minutes",
+        "pomodoro_long_break_minutes",
+        "pomodoro_long_break_every",
+    ] {
+        add_column(conn, "tasks", column, "INTEGER")?;
+    }
+
+    conn.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS pomodoro_state (
+            task_id TEXT PRIMARY KEY,
+            completed_focus_sessions INTEGER NOT NULL DEFAULT 0,
+            next_kind TEXT NOT NULL DEFAULT 'Focus',
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+        )
+        "#,
+        [],
+    )?;
+    Ok(())
+}
+
+fn session_flags(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "task_sessions", "needs_review", "BOOLEAN NOT NULL DEFAULT 0")?;
+    add_column(conn, "task_sessions", "is_manual", "BOOLEAN NOT NULL DEFAULT 0")?;
+    Ok(())
+}
+
+fn archive_and_trash(conn: &Connection) -> SqliteResult<()> {
+    for table in ["task_groups", "tasks"] {
+        add_column(conn, table, "archived_at", "TEXT")?;
+        add_column(conn, table, "deleted_at", "TEXT")?;
+    }
+    Ok(())
+}
+
+fn tags(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS task_tags (
+            task_id TEXT NOT NULL,
+            tag_id TEXT NOT NULL,
+            PRIMARY KEY (task_id, tag_id),
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_task_tags_tag ON task_tags (tag_id);
+        "#,
+    )
+}
+
+fn subtasks(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS subtasks (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            title TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            completed_at TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_subtasks_task ON subtasks (task_id, position);
+        "#,
+    )
+}
+
+fn task_status(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "tasks", "status", "TEXT NOT NULL DEFAULT 'Todo'")?;
+    add_column(conn, "tasks", "priority", "TEXT NOT NULL DEFAULT 'Medium'")?;
+    add_column(conn, "tasks", "due_date", "TEXT")?;
+    add_column(conn, "tasks", "completed_at", "TEXT")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_tasks_due_date ON tasks (due_date)", [])?;
+    Ok(())
+}
+
+fn task_estimates(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "tasks", "estimate_minutes", "INTEGER")?;
+    Ok(())
+}
+
+fn recurrence(conn: &Connection) -> SqliteResult<()> {
+    for (column, definition) in [
+        ("recurrence_rule", "TEXT"),
+        ("recurrence_days", "INTEGER"),
+        ("recurrence_interval_days", "INTEGER"),
+        ("recurrence_day_of_month", "INTEGER"),
+        ("recurrence_start_date", "TEXT"),
+        ("recurrence_target_minutes", "INTEGER"),
+        ("last_occurrence_date", "TEXT"),
+    ] {
+        add_column(conn, "tasks", column, definition)?;
+    }
+    Ok(())
+}
+
+// 按原先的显示顺序（最新的在前）回填
+fn sort_order(conn: &Connection) -> SqliteResult<()> {
+    if add_column(conn, "task_groups", "sort_order", "INTEGER NOT NULL DEFAULT 0")? {
+        conn.execute(
+            r#"
+            UPDATE task_groups SET sort_order = (
+                SELECT COUNT(*) FROM task_groups newer
+                WHERE newer.created_at > task_groups.created_at
+                   OR (newer.created_at = task_groups.created_at AND newer.id < task_groups.id)
+            )
+            "#,
+            [],
+        )?;
+    }
+    if add_column(conn, "tasks", "sort_order", "INTEGER NOT NULL DEFAULT 0")? {
+        conn.execute(
+            r#"
+            UPDATE tasks SET sort_order = (
+                SELECT COUNT(*) FROM tasks newer
+                WHERE newer.task_group_id = tasks.task_group_id
+                  AND (newer.created_at > tasks.created_at
+                       OR (newer.created_at = tasks.created_at AND newer.id < tasks.id))
+            )
+            "#,
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+// 彻底删除父任务组时，子任务组变为顶层
+fn nested_task_groups(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "task_groups", "parent_id", "TEXT REFERENCES task_groups (id) ON DELETE SET NULL")?;
+    conn.execute("CREATE INDEX IF NOT EXISTS idx_task_groups_parent ON task_groups (parent_id)", [])?;
+    Ok(())
+}
+
+fn task_group_appearance(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "task_groups", "color", "TEXT")?;
+    add_column(conn, "task_groups", "icon", "TEXT")?;
+    add_column(conn, "task_groups", "default_duration_minutes", "INTEGER")?;
+    add_column(conn, "task_groups", "default_timer_mode", "TEXT")?;
+    Ok(())
+}
+
+// 目标只关联一个对象，对象被删除时目标随之删除
+fn goals(conn: &Connection) -> SqliteResult<()> {
+    conn.execute_batch(
+        r#"
+        CREATE TABLE IF NOT EXISTS goals (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            scope TEXT NOT NULL,
+            task_id TEXT,
+            task_group_id TEXT,
+            tag_id TEXT,
+            period TEXT NOT NULL,
+            days_of_week INTEGER,
+            target_minutes INTEGER NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+            FOREIGN KEY (task_group_id) REFERENCES task_groups (id) ON DELETE CASCADE,
+            FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE,
+            CHECK ((task_id IS NOT NULL) + (task_group_id IS NOT NULL) + (tag_id IS NOT NULL) = 1)
+        );
+        "#,
+    )
+}
+
+fn goal_timezone(conn: &Connection) -> SqliteResult<()> {
+    add_column(conn, "goals", "timezone", "TEXT")?;
+    Ok(())
+}
+
+fn parse_time(idx: usize, value: &str) -> SqliteResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e)))
+}
+
+// 旧版本可能留下多个未结束的会话，建立唯一索引前只保留最新的一个（由启动时的恢复处理）。
+// 其余的最晚在下一个会话开始时结束，专注时长按计划时长或默认上限封顶，并标记为待确认
+fn single_active_session(conn: &Connection) -> SqliteResult<()> {
+    let now = Utc::now();
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT id, start_time, planned_minutes, is_paused, paused_at, total_paused_duration_ms
+        FROM task_sessions
+        WHERE end_time IS NULL
+        ORDER BY start_time ASC
+        "#,
+    )?;
+    let mut open_sessions = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                parse_time(1, &row.get::<_, String>(1)?)?,
+                row.get::<_, Option<i64>>(2)?,
+                row.get::<_, bool>(3)?,
+                row.get::<_, Option<String>>(4)?.map(|value| parse_time(4, &value)).transpose()?,
+                row.get::<_, i64>(5)?,
+            ))
+        })?
+        .collect::<SqliteResult<Vec<_>>>()?;
+    open_sessions.pop();
+
+    for (id, start_time, planned_minutes, is_paused, paused_at, total_paused_duration_ms) in open_sessions {
+        let next_start = conn
+            .query_row(
+                "SELECT MIN(start_time) FROM task_sessions WHERE start_time > ? AND id != ?",
+                rusqlite::params![start_time.to_rfc3339(), id],
+                |row| row.get::<_, Option<String>>(0),
+            )?
+            .map(|value| parse_time(0, &value))
+            .transpose()?;
+        let bound = next_start.unwrap_or(now).min(now);
+
+        let open_pause_ms = match (is_paused, paused_at) {
+            (true, Some(paused_at)) => (bound - paused_at).num_milliseconds().max(0),
+            _ => 0,
+        };
+        let focused_ms = ((bound - start_time).num_milliseconds() - total_paused_duration_ms - open_pause_ms).max(0);
+        let cap_ms = planned_minutes.unwrap_or(RECOVERED_SESSION_CAP_MINUTES) * 60_000;
+        let capped_ms = focused_ms.min(cap_ms);
+        let end_time = start_time + chrono::Duration::milliseconds(total_paused_duration_ms + capped_ms);
+        conn.execute(
+            r#"
+            UPDATE task_sessions
+            SET end_time = ?,
+                duration_minutes = ?,
+                duration_seconds = ?,
+                completed = 0,
+                outcome = 'AutoClosed',
+                is_paused = 0,
+                paused_at = NULL,
+                needs_review = 1
+            WHERE id = ?
+            "#,
+            rusqlite::params![end_time.to_rfc3339(), capped_ms / 60_000, capped_ms / 1000, id],
+        )?;
+    }
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_task_sessions_single_active ON task_sessions (IFNULL(end_time, '')) WHERE end_time IS NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Schema as created by v0.4.0 (`Database::init` before versioning)
+    const V0_4_SCHEMA: &str = r#"
+        CREATE TABLE task_groups (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );
+        CREATE TABLE tasks (
+            id TEXT PRIMARY KEY,
+            task_group_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            description TEXT,
+            duration_minutes INTEGER,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (task_group_id) REFERENCES task_groups (id) ON DELETE CASCADE
+        );
+        CREATE TABLE task_sessions (
+            id TEXT PRIMARY KEY,
+            task_id TEXT NOT NULL,
+            start_time TEXT NOT NULL,
+            end_time TEXT,
+            duration_minutes INTEGER,
+            completed BOOLEAN NOT NULL DEFAULT 0,
+            created_at TEXT NOT NULL,
+            is_paused BOOLEAN NOT NULL DEFAULT 0,
+            paused_at TEXT,
+            total_paused_duration_ms INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+        );
+    "#;
+
+    fn latest_version() -> u32 {
+        MIGRATIONS.last().unwrap().version
+    }
+
+    fn v0_4_database() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(V0_4_SCHEMA).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO task_groups VALUES ('g1', 'Work', NULL, '2024-05-01T08:00:00+00:00', '2024-05-01T08:00:00+00:00');
+            INSERT INTO tasks VALUES ('t1', 'g1', 'Write', NULL, 25, '2024-05-01T08:00:00+00:00', '2024-05-01T08:00:00+00:00');
+            INSERT INTO tasks VALUES ('t2', 'g1', 'Read', NULL, NULL, '2024-05-01T08:00:00+00:00', '2024-05-01T08:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, completed, created_at)
+                VALUES ('s1', 't1', '2024-05-01T09:00:00+00:00', '2024-05-01T09:25:00+00:00', 25, 1, '2024-05-01T09:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, completed, created_at)
+                VALUES ('s2', 't1', '2024-05-01T10:00:00+00:00', '2024-05-01T10:10:00+00:00', 10, 1, '2024-05-01T10:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, completed, created_at)
+                VALUES ('s3', 't2', '2024-05-01T11:00:00+00:00', '2024-05-01T11:40:00+00:00', 40, 1, '2024-05-01T11:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, end_time, duration_minutes, completed, created_at, total_paused_duration_ms)
+                VALUES ('s4', 't2', '2024-05-01T11:50:00+00:00', '2024-05-01T12:20:00+00:00', NULL, 1, '2024-05-01T11:50:00+00:00', 300000);
+            "#,
+        )
+        .unwrap();
+        conn
+    }
+
+    fn session_row(conn: &Connection, id: &str) -> (Option<i64>, Option<String>, bool, String, Option<i32>) {
+        conn.query_row(
+            "SELECT duration_seconds, outcome, completed, kind, planned_minutes FROM task_sessions WHERE id = ?",
+            [id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn upgrades_v0_4_database_to_latest() {
+        let mut conn = v0_4_database();
+        assert_eq!(schema_version(&conn).unwrap(), 0);
+
+        run(&mut conn).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        for column in ["duration_seconds", "outcome", "kind", "planned_minutes", "needs_review", "is_manual"] {
+            assert!(has_column(&conn, "task_sessions", column).unwrap(), "missing {}", column);
+        }
+        assert!(has_column(&conn, "tasks", "pomodoro_work_minutes").unwrap());
+        assert!(has_column(&conn, "pomodoro_state", "next_kind").unwrap());
+        assert!(has_column(&conn, "tasks", "deleted_at").unwrap());
+        assert!(has_column(&conn, "task_groups", "archived_at").unwrap());
+        assert!(has_column(&conn, "task_tags", "tag_id").unwrap());
+        assert!(has_column(&conn, "subtasks", "position").unwrap());
+        assert!(has_column(&conn, "goals", "timezone").unwrap());
+        let status: String = conn
+            .query_row("SELECT status FROM tasks WHERE id = 't1'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(status, "Todo");
+        let orders: Vec<i32> = conn
+            .prepare("SELECT sort_order FROM tasks ORDER BY id")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<SqliteResult<_>>()
+            .unwrap();
+        assert_eq!(orders, vec![0, 1]);
+
+        assert_eq!(
+            session_row(&conn, "s1"),
+            (Some(1500), Some("Completed".to_string()), true, "Focus".to_string(), Some(25))
+        );
+        assert_eq!(
+            session_row(&conn, "s2"),
+            (Some(600), Some("Abandoned".to_string()), false, "Focus".to_string(), Some(25))
+        );
+        assert_eq!(
+            session_row(&conn, "s3"),
+            (Some(2400), Some("Completed".to_string()), true, "Focus".to_string(), None)
+        );
+        // 没有分钟数的旧会话：30 分钟减去 5 分钟暂停
+        assert_eq!(
+            session_row(&conn, "s4"),
+            (Some(1500), Some("Completed".to_string()), true, "Focus".to_string(), None)
+        );
+    }
+
+    // v0.4 允许同时存在多个未结束的会话，唯一索引建立前只保留最新的一个
+    #[test]
+    fn closes_duplicate_open_sessions_before_indexing() {
+        let mut conn = v0_4_database();
+        conn.execute_batch(
+            r#"
+            INSERT INTO task_sessions (id, task_id, start_time, completed, created_at)
+                VALUES ('o1', 't1', '2024-05-02T09:00:00+00:00', 0, '2024-05-02T09:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, completed, created_at)
+                VALUES ('o2', 't2', '2024-05-02T10:00:00+00:00', 0, '2024-05-02T10:00:00+00:00');
+            INSERT INTO task_sessions (id, task_id, start_time, completed, created_at)
+                VALUES ('o3', 't2', '2024-05-02T11:00:00+00:00', 0, '2024-05-02T11:00:00+00:00');
+            "#,
+        )
+        .unwrap();
+
+        run(&mut conn).unwrap();
+
+        let closed = |id: &str| -> (Option<String>, Option<i64>, Option<String>, bool) {
+            conn.query_row(
+                "SELECT end_time, duration_seconds, outcome, needs_review FROM task_sessions WHERE id = ?",
+                [id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap()
+        };
+        // 倒计时会话按计划的 25 分钟封顶
+        assert_eq!(
+            closed("o1"),
+            (Some("2024-05-02T09:25:00+00:00".to_string()), Some(1500), Some("AutoClosed".to_string()), true)
+        );
+        // 正向计时会话在下一个会话开始时结束
+        assert_eq!(
+            closed("o2"),
+            (Some("2024-05-02T11:00:00+00:00".to_string()), Some(3600), Some("AutoClosed".to_string()), true)
+        );
+        assert_eq!(closed("o3"), (None, None, None, false));
+
+        let second_active = conn.execute(
+            "INSERT INTO task_sessions (id, task_id, start_time, completed, created_at) VALUES ('o4', 't1', ?1, 0, ?1)",
+            ["2024-05-03T09:00:00+00:00"],
+        );
+        assert!(second_active.is_err());
+    }
+
+    #[test]
+    fn upgrade_is_idempotent() {
+        let mut conn = v0_4_database();
+        run(&mut conn).unwrap();
+        run(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert_eq!(session_row(&conn, "s2").1, Some("Abandoned".to_string()));
+    }
+
+    #[test]
+    fn creates_fresh_database() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        run(&mut conn).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version());
+        assert!(has_column(&conn, "task_sessions", "is_manual").unwrap());
+    }
+
+    #[test]
+    fn rejects_newer_schema() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(matches!(run(&mut conn), Err(AppError::Conflict(_))));
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back_and_reported() {
+        fn create_widgets(conn: &Connection) -> SqliteResult<()> {
+            conn.execute("CREATE TABLE widgets (id TEXT PRIMARY KEY)", [])?;
+            Ok(())
+        }
+        fn broken(conn: &Connection) -> SqliteResult<()> {
+            conn.execute("ALTER TABLE widgets ADD COLUMN size INTEGER", [])?;
+            conn.execute("ALTER TABLE missing_table ADD COLUMN size INTEGER", [])?;
+            Ok(())
+        }
+        let migrations = [
+            Migration { version: 1, description: "widgets", up: create_widgets },
+            Migration { version: 2, description: "broken", up: broken },
+        ];
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        let result = run_migrations(&mut conn, &migrations);
+
+        assert!(matches!(result, Err(AppError::Migration { version: 2, .. })));
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        assert!(!has_column(&conn, "widgets", "size").unwrap());
+    }
+}
@@ -1,5 +1,5 @@
-use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskGroup {
@@ -8,6 +8,43 @@ pub struct TaskGroup {
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>, // Set while the group is in the trash
+    pub sort_order: i32, // Manual position in get_task_groups, ascending
+    pub parent_id: Option<String>, // None for top-level groups
+    pub color: Option<String>, // CSS color, as produced by the color picker
+    pub icon: Option<String>, // Emoji or short icon name
+    pub default_duration_minutes: Option<i32>, // Used by create_task when the request has no duration
+    pub default_timer_mode: Option<TimerMode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimerMode {
+    Countdown,
+    Forward,
+}
+
+impl TimerMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimerMode::Countdown => "Countdown",
+            TimerMode::Forward => "Forward",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Countdown" => Some(TimerMode::Countdown),
+            "Forward" => Some(TimerMode::Forward),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGroupNode {
+    pub task_group: TaskGroup,
+    pub children: Vec<TaskGroupNode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,10 +54,161 @@ pub struct Task {
     pub name: String,
     pub description: Option<String>,
     pub duration_minutes: Option<i32>, // None for forward timing
+    pub pomodoro: Option<PomodoroConfig>, // None when the task doesn't use Pomodoro cycles
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>, // Set while the task is in the trash
+    pub status: TaskStatus,
+    pub priority: TaskPriority,
+    pub due_date: Option<NaiveDate>,
+    pub completed_at: Option<DateTime<Utc>>, // Set while the status is Done
+    pub estimate_minutes: Option<i32>, // Expected total focus time, independent of the timer length
+    pub recurrence: Option<Recurrence>, // None for one-off tasks
+    pub last_occurrence_date: Option<NaiveDate>, // Day the scheduler last reset the routine
+    pub sort_order: i32, // Manual position within the group, ascending
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RecurrenceRule {
+    Daily,
+    Weekdays,
+    Weekly,
+    EveryNDays,
+    Monthly,
+}
+
+impl RecurrenceRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecurrenceRule::Daily => "Daily",
+            RecurrenceRule::Weekdays => "Weekdays",
+            RecurrenceRule::Weekly => "Weekly",
+            RecurrenceRule::EveryNDays => "EveryNDays",
+            RecurrenceRule::Monthly => "Monthly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Daily" => Some(RecurrenceRule::Daily),
+            "Weekdays" => Some(RecurrenceRule::Weekdays),
+            "Weekly" => Some(RecurrenceRule::Weekly),
+            "EveryNDays" => Some(RecurrenceRule::EveryNDays),
+            "Monthly" => Some(RecurrenceRule::Monthly),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Recurrence {
+    pub rule: RecurrenceRule,
+    pub days_of_week: Option<Vec<Weekday>>, // Weekly only, e.g. ["Mon", "Thu"]
+    pub interval_days: Option<i32>, // EveryNDays only
+    pub day_of_month: Option<i32>, // Monthly only; short months use their last day
+    pub start_date: Option<NaiveDate>, // First possible occurrence; defaults to the creation day
+    pub target_minutes: Option<i32>, // Focus time per occurrence; defaults to duration_minutes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroConfig {
+    pub work_minutes: i32,
+    pub short_break_minutes: i32,
+    pub long_break_minutes: i32,
+    pub long_break_every: i32, // Number of focus sessions before a long break
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtask {
+    pub id: String,
+    pub task_id: String,
+    pub title: String,
+    pub position: i32, // Checklist order within the task, ascending
+    pub completed: bool,
+    pub completed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ChecklistProgress {
+    pub total_items: i32,
+    pub completed_items: i32,
+    pub progress: f64, // 0.0 - 1.0; 0.0 when the task has no items
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoalScope {
+    Task,
+    TaskGroup, // Includes the group's subgroups
+    Tag,
+}
+
+impl GoalScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalScope::Task => "Task",
+            GoalScope::TaskGroup => "TaskGroup",
+            GoalScope::Tag => "Tag",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Task" => Some(GoalScope::Task),
+            "TaskGroup" => Some(GoalScope::TaskGroup),
+            "Tag" => Some(GoalScope::Tag),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GoalPeriod {
+    Day,
+    Week, // Weeks start on Monday
+}
+
+impl GoalPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalPeriod::Day => "Day",
+            GoalPeriod::Week => "Week",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Day" => Some(GoalPeriod::Day),
+            "Week" => Some(GoalPeriod::Week),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Goal {
+    pub id: String,
+    pub name: String,
+    pub scope: GoalScope,
+    pub scope_id: String, // Id of the task, task group or tag
+    pub period: GoalPeriod,
+    pub days_of_week: Option<Vec<Weekday>>, // Day goals only; None means every day
+    pub target_minutes: i32, // Focus time per period
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub timezone: Option<String>, // IANA name deciding where days and weeks start; defaults to the system timezone
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskSession {
     pub id: String,
@@ -28,23 +216,262 @@ pub struct TaskSession {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub duration_minutes: Option<i32>, // Actual duration for forward timing
+    pub duration_seconds: Option<i64>, // Focused duration computed when the session ends
     pub completed: bool,
+    pub outcome: Option<SessionOutcome>, // None while the session is still running
+    pub kind: SessionKind,
+    pub planned_minutes: Option<i32>, // Countdown target, None for forward timing
     pub is_paused: bool,
     pub paused_at: Option<DateTime<Utc>>,
     pub total_paused_duration_ms: i64, // Total paused duration in milliseconds
+    pub needs_review: bool, // Auto-closed by crash recovery, awaiting user confirmation
+    pub is_manual: bool, // Entered or edited by hand rather than tracked by the timer
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskStatus {
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "Todo",
+            TaskStatus::InProgress => "InProgress",
+            TaskStatus::Done => "Done",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Todo" => Some(TaskStatus::Todo),
+            "InProgress" => Some(TaskStatus::InProgress),
+            "Done" => Some(TaskStatus::Done),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskPriority {
+    Low,
+    Medium,
+    High,
+}
+
+impl TaskPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskPriority::Low => "Low",
+            TaskPriority::Medium => "Medium",
+            TaskPriority::High => "High",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Low" => Some(TaskPriority::Low),
+            "Medium" => Some(TaskPriority::Medium),
+            "High" => Some(TaskPriority::High),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskSortBy {
+    Manual,    // By sort_order
+    CreatedAt, // Newest first
+    Priority,  // Highest first, then earliest due date
+    DueDate,   // Earliest first, tasks without a due date last
+    Status,    // In progress, todo, done
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaskFilter {
+    pub include_archived: Option<bool>,
+    pub tag_id: Option<String>,
+    pub statuses: Option<Vec<TaskStatus>>, // None or empty keeps every status
+    pub priority: Option<TaskPriority>,
+    pub sort_by: Option<TaskSortBy>, // Defaults to Manual
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueTasksRequest {
+    pub timezone: Option<String>, // Decides which day counts as today
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutineProgress {
+    pub task: Task,
+    pub task_group_name: String,
+    pub target_minutes: Option<i32>,
+    pub focused_minutes: i32,
+    pub focused_seconds: i64,
+    pub target_met: bool, // Without a target, any focus time counts
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueRoutines {
+    pub date: NaiveDate,
+    pub routines: Vec<RoutineProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DueTasks {
+    pub date: NaiveDate,
+    pub overdue: Vec<Task>,
+    pub due_today: Vec<Task>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionKind {
+    Focus,
+    ShortBreak,
+    LongBreak,
+}
+
+impl SessionKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionKind::Focus => "Focus",
+            SessionKind::ShortBreak => "ShortBreak",
+            SessionKind::LongBreak => "LongBreak",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Focus" => Some(SessionKind::Focus),
+            "ShortBreak" => Some(SessionKind::ShortBreak),
+            "LongBreak" => Some(SessionKind::LongBreak),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionOutcome {
+    Completed,
+    Abandoned,
+    Interrupted,
+    AutoClosed,
+}
+
+impl SessionOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionOutcome::Completed => "Completed",
+            SessionOutcome::Abandoned => "Abandoned",
+            SessionOutcome::Interrupted => "Interrupted",
+            SessionOutcome::AutoClosed => "AutoClosed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "Completed" => Some(SessionOutcome::Completed),
+            "Abandoned" => Some(SessionOutcome::Abandoned),
+            "Interrupted" => Some(SessionOutcome::Interrupted),
+            "AutoClosed" => Some(SessionOutcome::AutoClosed),
+            _ => None,
+        }
+    }
+}
+
+impl TaskSession {
+    /// Focused time up to `end_time` (or `now` while running): wall time since
+    /// start minus every paused interval, including one that is still open.
+    pub fn focused_ms(&self, now: DateTime<Utc>) -> i64 {
+        let end = self.end_time.unwrap_or(now);
+        let open_pause_ms = match (self.is_paused, self.paused_at) {
+            (true, Some(paused_at)) => (end - paused_at).num_milliseconds().max(0),
+            _ => 0,
+        };
+        ((end - self.start_time).num_milliseconds() - self.total_paused_duration_ms - open_pause_ms).max(0)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateTaskGroupRequest {
     pub name: String,
     pub description: Option<String>,
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub default_duration_minutes: Option<i32>,
+    pub default_timer_mode: Option<TimerMode>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A field in a partial update: omitted from the request, explicitly `null`, or a new value.
+/// Fields of this type need `#[serde(default)]` so an omitted key becomes `Absent`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Patch<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Patch::Absent)
+    }
+
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Patch::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Patch<U> {
+        match self {
+            Patch::Absent => Patch::Absent,
+            Patch::Null => Patch::Null,
+            Patch::Value(value) => Patch::Value(f(value)),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // 字段缺失时由 #[serde(default)] 得到 Absent，这里只会遇到 null 或具体值
+        Ok(match Option::<T>::deserialize(deserializer)? {
+            Some(value) => Patch::Value(value),
+            None => Patch::Null,
+        })
+    }
+}
+
+impl<T: Serialize> Serialize for Patch<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Patch::Value(value) => value.serialize(serializer),
+            _ => serializer.serialize_none(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UpdateTaskGroupRequest {
-    pub name: Option<String>,
-    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub name: Patch<String>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub description: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub parent_id: Patch<String>, // null makes the group top-level
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub color: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub icon: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub default_duration_minutes: Patch<i32>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub default_timer_mode: Patch<TimerMode>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,49 +479,344 @@ pub struct CreateTaskRequest {
     pub task_group_id: String,
     pub name: String,
     pub description: Option<String>,
-    pub duration_minutes: Option<i32>,
+    pub duration_minutes: Option<i32>, // None inherits the group's default timer settings
+    pub timer_mode: Option<TimerMode>, // Forward opts out of the group's default duration
+    pub pomodoro: Option<PomodoroConfig>,
+    pub priority: Option<TaskPriority>, // Defaults to Medium
+    pub due_date: Option<NaiveDate>,
+    pub estimate_minutes: Option<i32>,
+    pub recurrence: Option<Recurrence>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UpdateTaskRequest {
-    pub name: Option<String>,
-    pub description: Option<String>,
-    pub duration_minutes: Option<i32>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub task_group_id: Patch<String>, // Moves the task and its sessions; cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub name: Patch<String>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub description: Patch<String>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub duration_minutes: Patch<i32>, // null switches the task to a forward timer
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub pomodoro: Patch<PomodoroConfig>, // null turns Pomodoro cycles off
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub priority: Patch<TaskPriority>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub due_date: Patch<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub estimate_minutes: Patch<i32>,
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub recurrence: Patch<Recurrence>, // null turns the task back into a one-off
+}
+
+// Listed items take the first positions in the given order; items left out
+// (e.g. archived ones the UI hides) keep their relative order after them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderTasksRequest {
+    pub task_group_id: String,
+    pub task_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderTaskGroupsRequest {
+    pub task_group_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetTaskStatusRequest {
+    pub task_id: String,
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateTagRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetTaskTagsRequest {
+    pub task_id: String,
+    pub tag_ids: Vec<String>, // Replaces the task's current tags
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddSubtaskRequest {
+    pub task_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReorderSubtasksRequest {
+    pub task_id: String,
+    pub subtask_ids: Vec<String>, // Every subtask of the task, in the new order
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateGoalRequest {
+    pub name: String,
+    pub scope: GoalScope,
+    pub scope_id: String,
+    pub period: GoalPeriod,
+    pub days_of_week: Option<Vec<Weekday>>,
+    pub target_minutes: i32,
+    pub timezone: Option<String>,
+}
+
+// The scope of a goal is fixed; create a new goal to track something else
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UpdateGoalRequest {
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub name: Patch<String>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub period: Patch<GoalPeriod>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub days_of_week: Patch<Vec<Weekday>>, // null counts every day
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub target_minutes: Patch<i32>, // Cannot be null
+    #[serde(default, skip_serializing_if = "Patch::is_absent")]
+    pub timezone: Patch<String>, // null switches back to the system timezone
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartSessionRequest {
     pub task_id: String,
+    pub kind: Option<SessionKind>, // Defaults to the next phase of the task's Pomodoro cycle
+    pub on_active: Option<ActiveSessionPolicy>, // Defaults to Reject
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ActiveSessionPolicy {
+    Reject, // Fail with SessionAlreadyActive
+    Switch, // Interrupt the active session and start the new one
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EndSessionRequest {
     pub session_id: String,
-    pub duration_minutes: Option<i32>, // For forward timing
+    pub duration_minutes: Option<i32>, // Overrides the server-computed duration; at most the focused time
+    pub outcome: Option<SessionOutcome>, // Inferred from the task target when omitted
 }
 
-#[allow(dead_code)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PauseSessionRequest {
     pub session_id: String,
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResumeSessionRequest {
     pub session_id: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateManualSessionRequest {
+    pub task_id: String,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub duration_minutes: Option<i32>, // Focused minutes, defaults to end_time - start_time
+    pub outcome: Option<SessionOutcome>, // Defaults to Completed
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateSessionRequest {
+    pub task_id: Option<String>,
+    pub start_time: Option<DateTime<Utc>>,
+    pub end_time: Option<DateTime<Utc>>,
+    pub duration_minutes: Option<i32>, // Recomputed from the new times when omitted
+    pub outcome: Option<SessionOutcome>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListSessionsRequest {
+    pub start_date: Option<String>, // RFC3339 instant or YYYY-MM-DD
+    pub end_date: Option<String>, // A YYYY-MM-DD end date includes that whole day
+    pub task_id: Option<String>,
+    pub task_group_id: Option<String>,
+    pub tag_id: Option<String>, // Sessions of tasks carrying this tag
+    pub outcome: Option<SessionOutcome>,
+    pub sort: Option<SortDirection>, // Defaults to newest first
+    pub cursor: Option<String>, // next_cursor from the previous page
+    pub limit: Option<u32>,
+    pub timezone: Option<String>, // IANA name for date bounds; defaults to the system timezone
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolveRecoveredSessionRequest {
+    pub session_id: String,
+    pub outcome: Option<SessionOutcome>, // Keeps AutoClosed when omitted
+    pub duration_minutes: Option<i32>, // Keeps the capped duration when omitted
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroState {
+    pub task_id: String,
+    pub completed_focus_sessions: i32, // Focus sessions completed in the current cycle
+    pub long_break_every: i32,
+    pub next_kind: SessionKind,
+    pub next_minutes: i32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatisticsRequest {
     pub start_date: String,
     pub end_date: String,
     pub group_by: StatisticsGroupBy,
+    pub include_manual: Option<bool>, // Defaults to true
+    pub timezone: Option<String>, // IANA name such as "Asia/Shanghai"; defaults to the system timezone
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StatisticsGroupBy {
     Task,
     TaskGroup,
+    Tag,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeBucket {
+    Day,
+    Week, // Weeks start on Monday
+    Month,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSeriesRequest {
+    pub start_date: String,
+    pub end_date: String,
+    pub bucket: TimeBucket,
+    pub by_task_group: Option<bool>, // Adds a per-group breakdown to every bucket
+    pub include_manual: Option<bool>, // Defaults to true
+    pub timezone: Option<String>, // IANA name; defaults to the system timezone
+}
+
+// Actual focus time of a task across all of its focus sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEffort {
+    pub task_id: String,
+    pub estimate_minutes: Option<i32>,
+    pub total_sessions: i32,
+    pub actual_minutes: i32,
+    pub actual_seconds: i64,
+    pub remaining_minutes: Option<i32>, // Negative once the estimate is exceeded
+    pub ratio: Option<f64>, // actual / estimate
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateReportRequest {
+    pub start_date: String,
+    pub end_date: String,
+    pub include_manual: Option<bool>, // Defaults to true
+    pub timezone: Option<String>,
+}
+
+// Covers tasks with an estimate that were worked on in the period. The
+// actual time counts every focus session up to the end of the period,
+// since the estimate is for the whole task.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskEstimateAccuracy {
+    pub task_id: String,
+    pub task_name: String,
+    pub task_group_id: String,
+    pub task_group_name: String,
+    pub status: TaskStatus,
+    pub estimate_minutes: i32,
+    pub actual_minutes: i32,
+    pub actual_seconds: i64,
+    pub period_seconds: i64, // Part of the actual time that falls in the period
+    pub variance_minutes: i32, // Positive when over the estimate
+    pub ratio: f64, // actual / estimate; above 1.0 is over
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGroupEstimateAccuracy {
+    pub task_group_id: String,
+    pub task_group_name: String,
+    pub estimated_tasks: i32,
+    pub over_estimate_tasks: i32,
+    pub under_estimate_tasks: i32,
+    pub estimate_minutes: i32,
+    pub actual_minutes: i32,
+    pub actual_seconds: i64,
+    pub ratio: f64, // Total actual / total estimate
+    pub over_ratio: f64, // Share of tasks over their estimate
+    pub under_ratio: f64, // Share of tasks under their estimate
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EstimateReport {
+    pub tasks: Vec<TaskEstimateAccuracy>,
+    pub task_groups: Vec<TaskGroupEstimateAccuracy>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgressRequest {
+    pub goal_id: Option<String>, // Defaults to every goal
+    pub history_periods: Option<u32>, // Past periods to include; defaults to 7
+    pub include_manual: Option<bool>, // Defaults to true
+}
+
+// Past periods are measured against the goal's current target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalPeriodProgress {
+    pub period_start: NaiveDate, // The day, or the Monday of the week
+    pub focused_minutes: i32,
+    pub focused_seconds: i64,
+    pub target_minutes: i32,
+    pub progress: f64, // focused / target; can exceed 1.0
+    pub met: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub scope_name: String,
+    pub current: Option<GoalPeriodProgress>, // None on days a Day goal skips
+    pub history: Vec<GoalPeriodProgress>, // Most recent first, skipped days left out
+}
+
+// Payload of the goal-met event, sent once when a session crosses the target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalMet {
+    pub goal: Goal,
+    pub scope_name: String,
+    pub session_id: String,
+    pub period: GoalPeriodProgress,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGroupSeriesValue {
+    pub task_group_id: String,
+    pub task_group_name: String,
+    pub task_group_color: Option<String>,
+    pub total_sessions: i32,
+    pub total_duration_minutes: i32,
+    pub total_duration_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSeriesPoint {
+    pub bucket_start: NaiveDate,
+    pub total_sessions: i32,
+    pub total_duration_minutes: i32,
+    pub total_duration_seconds: i64,
+    pub task_groups: Vec<TaskGroupSeriesValue>, // Empty unless by_task_group is set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeSeriesResponse {
+    pub bucket: TimeBucket,
+    pub points: Vec<TimeSeriesPoint>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,9 +824,15 @@ pub struct TaskStatistics {
     pub task_id: String,
     pub task_name: String,
     pub task_group_name: String,
+    pub task_group_color: Option<String>,
     pub total_sessions: i32,
     pub total_duration_minutes: i32,
+    pub total_duration_seconds: i64,
     pub completed_sessions: i32,
+    pub abandoned_sessions: i32,
+    pub interrupted_sessions: i32,
+    pub auto_closed_sessions: i32,
+    pub manual_sessions: i32,
     pub completion_rate: f64,
 }
 
@@ -112,10 +840,42 @@ pub struct TaskStatistics {
 pub struct TaskGroupStatistics {
     pub task_group_id: String,
     pub task_group_name: String,
+    pub parent_id: Option<String>,
+    pub color: Option<String>,
     pub total_tasks: i32,
     pub total_sessions: i32,
     pub total_duration_minutes: i32,
+    pub total_duration_seconds: i64,
     pub completed_sessions: i32,
+    pub abandoned_sessions: i32,
+    pub interrupted_sessions: i32,
+    pub auto_closed_sessions: i32,
+    pub manual_sessions: i32,
+    pub completion_rate: f64,
+    // The group's own totals above, plus those of all its descendants
+    pub rollup_tasks: i32,
+    pub rollup_sessions: i32,
+    pub rollup_duration_minutes: i32,
+    pub rollup_duration_seconds: i64,
+    pub rollup_completed_sessions: i32,
+    pub rollup_completion_rate: f64,
+}
+
+// A session of a task with several tags counts towards each of them,
+// so tag totals can add up to more than the overall focus time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagStatistics {
+    pub tag_id: String,
+    pub tag_name: String,
+    pub total_tasks: i32,
+    pub total_sessions: i32,
+    pub total_duration_minutes: i32,
+    pub total_duration_seconds: i64,
+    pub completed_sessions: i32,
+    pub abandoned_sessions: i32,
+    pub interrupted_sessions: i32,
+    pub auto_closed_sessions: i32,
+    pub manual_sessions: i32,
     pub completion_rate: f64,
 }
 
@@ -123,6 +883,13 @@ pub struct TaskGroupStatistics {
 pub struct StatisticsResponse {
     pub task_statistics: Vec<TaskStatistics>,
     pub task_group_statistics: Vec<TaskGroupStatistics>,
+    pub tag_statistics: Vec<TagStatistics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trash {
+    pub task_groups: Vec<TaskGroup>,
+    pub tasks: Vec<Task>, // Tasks trashed on their own, not through their group
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,4 +897,69 @@ pub struct ActiveSession {
     pub session: TaskSession,
     pub task: Task,
     pub task_group: TaskGroup,
-}
\ No newline at end of file
+    pub checklist: ChecklistProgress,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub session_id: String,
+    pub task_id: String,
+    pub kind: SessionKind,
+    pub elapsed_ms: i64,
+    pub remaining_ms: Option<i64>, // None for forward timing
+    pub is_paused: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDetail {
+    pub session: TaskSession,
+    pub task: Task,
+    pub task_group: TaskGroup,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionPage {
+    pub sessions: Vec<SessionDetail>,
+    pub next_cursor: Option<String>, // None on the last page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patch_distinguishes_absent_null_and_value() {
+        let absent: UpdateTaskRequest = serde_json::from_str("{}").unwrap();
+        assert_eq!(absent.name, Patch::Absent);
+        assert_eq!(absent.duration_minutes, Patch::Absent);
+
+        let null: UpdateTaskRequest =
+            serde_json::from_str(r#"{"description": null, "duration_minutes": null, "pomodoro": null}"#).unwrap();
+        assert_eq!(null.name, Patch::Absent);
+        assert_eq!(null.description, Patch::Null);
+        assert_eq!(null.duration_minutes, Patch::Null);
+        assert!(matches!(null.pomodoro, Patch::Null));
+
+        let value: UpdateTaskRequest = serde_json::from_str(r#"{"name": "Read", "duration_minutes": 30}"#).unwrap();
+        assert_eq!(value.name, Patch::Value("Read".to_string()));
+        assert_eq!(value.duration_minutes, Patch::Value(30));
+        assert_eq!(value.description, Patch::Absent);
+    }
+
+    #[test]
+    fn patch_serializes_only_present_fields() {
+        let request = UpdateTaskGroupRequest {
+            name: Patch::Value("Work".to_string()),
+            description: Patch::Null,
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            serde_json::json!({ "name": "Work", "description": null })
+        );
+        assert_eq!(
+            serde_json::to_value(UpdateTaskGroupRequest::default()).unwrap(),
+            serde_json::json!({})
+        );
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use crate::error::{AppError, AppResult};
+use crate::models::{Recurrence, RecurrenceRule};
+
+const WEEK: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+pub fn validate(recurrence: &Recurrence) -> AppResult<()> {
+    match recurrence.rule {
+        RecurrenceRule::Weekly if recurrence.days_of_week.as_deref().unwrap_or_default().is_empty() => {
+            return Err(AppError::Validation("weekly recurrence needs days_of_week".to_string()));
+        }
+        RecurrenceRule::EveryNDays if !matches!(recurrence.interval_days, Some(days) if days > 0) => {
+            return Err(AppError::Validation("interval_days must be positive".to_string()));
+        }
+        RecurrenceRule::Monthly if !matches!(recurrence.day_of_month, Some(1..=31)) => {
+            return Err(AppError::Validation("day_of_month must be between 1 and 31".to_string()));
+        }
+        _ => {}
+    }
+    if let Some(minutes) = recurrence.target_minutes {
+        if minutes <= 0 {
+            return Err(AppError::Validation("target_minutes must be positive".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the routine has an occurrence on `date`. `anchor` is the first
+/// day it can occur and the day EveryNDays counts from.
+pub fn occurs_on(recurrence: &Recurrence, anchor: NaiveDate, date: NaiveDate) -> bool {
+    if date < anchor {
+        return false;
+    }
+    match recurrence.rule {
+        RecurrenceRule::Daily => true,
+        RecurrenceRule::Weekdays => !matches!(date.weekday(), Weekday::Sat | Weekday::Sun),
+        RecurrenceRule::Weekly => recurrence
+            .days_of_week
+            .as_deref()
+            .unwrap_or_default()
+            .contains(&date.weekday()),
+        RecurrenceRule::EveryNDays => {
+            let interval = recurrence.interval_days.unwrap_or(1).max(1) as i64;
+            (date - anchor).num_days() % interval == 0
+        }
+        // 31 号之类的日期在较短的月份落到最后一天
+        RecurrenceRule::Monthly => {
+            let day = recurrence.day_of_month.unwrap_or(1) as u32;
+            date.day() == day.min(days_in_month(date))
+        }
+    }
+}
+
+fn days_in_month(date: NaiveDate) -> u32 {
+    let first = date.with_day(1).unwrap();
+    let next = first + Months::new(1);
+    (next - first).num_days() as u32
+}
+
+/// Weekdays are stored as a bit mask, Monday being the lowest bit.
+pub fn days_to_mask(days: &[Weekday]) -> i32 {
+    days.iter().fold(0, |mask, day| mask | 1 << day.num_days_from_monday())
+}
+
+pub fn mask_to_days(mask: i32) -> Vec<Weekday> {
+    WEEK.into_iter()
+        .filter(|day| mask & (1 << day.num_days_from_monday()) != 0)
+        .collect()
+}
@@ -0,0 +1,303 @@
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use crate::error::{AppError, AppResult};
+use crate::models::{SessionOutcome, TimeBucket};
+
+/// Timezone used to decide which calendar day a moment belongs to.
+#[derive(Debug, Clone, Copy)]
+pub enum Zone {
+    Named(Tz),
+    Local, // 未指定时区时使用系统本地时区
+}
+
+impl Zone {
+    pub fn resolve(name: Option<&str>) -> AppResult<Zone> {
+        match name {
+            Some(name) => name
+                .parse::<Tz>()
+                .map(Zone::Named)
+                .map_err(|_| AppError::Validation(format!("unknown timezone: {}", name))),
+            None => Ok(Zone::Local),
+        }
+    }
+
+    pub fn local_date(&self, time: DateTime<Utc>) -> NaiveDate {
+        match self {
+            Zone::Named(tz) => time.with_timezone(tz).date_naive(),
+            Zone::Local => time.with_timezone(&Local).date_naive(),
+        }
+    }
+
+    pub fn start_of_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        match self {
+            Zone::Named(tz) => start_of_day_in(tz, date),
+            Zone::Local => start_of_day_in(&Local, date),
+        }
+    }
+}
+
+fn start_of_day_in<T: TimeZone>(tz: &T, date: NaiveDate) -> DateTime<Utc> {
+    let mut time = date.and_time(NaiveTime::MIN);
+    // 夏令时切换可能跳过午夜，顺延到当天第一个存在的时刻
+    loop {
+        if let Some(local) = tz.from_local_datetime(&time).earliest() {
+            return local.with_timezone(&Utc);
+        }
+        time += Duration::minutes(15);
+    }
+}
+
+/// Resolves a request range to a half-open UTC interval `[start, end)`.
+/// Accepts RFC3339 instants or YYYY-MM-DD dates; dates are local calendar
+/// days in `zone` and the end date is included in full.
+pub fn parse_range(start: &str, end: &str, zone: Zone) -> AppResult<(DateTime<Utc>, DateTime<Utc>)> {
+    let range_start = parse_bound(start, false, zone)?;
+    let range_end = parse_bound(end, true, zone)?;
+    if range_end <= range_start {
+        return Err(AppError::Validation("end_date must be after start_date".to_string()));
+    }
+    Ok((range_start, range_end))
+}
+
+/// A single range bound; an end date resolves to the following local midnight.
+pub fn parse_bound(value: &str, is_end: bool, zone: Zone) -> AppResult<DateTime<Utc>> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::Validation(format!("invalid date: {}", value)))?;
+    let date = if is_end { date + Duration::days(1) } else { date };
+    Ok(zone.start_of_day(date))
+}
+
+/// Splits `[start, end)` at local midnights, returning each local day with
+/// the milliseconds of the interval that fall on it.
+pub fn split_by_day(zone: Zone, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<(NaiveDate, i64)> {
+    let mut days = Vec::new();
+    let mut current = start;
+    while current < end {
+        let date = zone.local_date(current);
+        let next_midnight = zone.start_of_day(date + Duration::days(1)).min(end);
+        days.push((date, (next_midnight - current).num_milliseconds()));
+        current = next_midnight;
+    }
+    days
+}
+
+/// Share of `duration_seconds` proportional to `part_ms` out of the session's wall-clock span.
+pub fn prorate(duration_seconds: i64, part_ms: i64, span_ms: i64) -> i64 {
+    if span_ms <= 0 {
+        return duration_seconds;
+    }
+    duration_seconds * part_ms / span_ms
+}
+
+/// A finished focus session, clipped to the statistics range.
+pub struct ClippedSession {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub span_ms: i64,
+    pub duration_seconds: i64,
+}
+
+impl ClippedSession {
+    /// Returns `None` when the session lies entirely outside `[range_start, range_end)`.
+    pub fn clip(
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        duration_seconds: i64,
+        range_start: DateTime<Utc>,
+        range_end: DateTime<Utc>,
+    ) -> Option<ClippedSession> {
+        let span_ms = (end - start).num_milliseconds();
+        let clipped_start = start.max(range_start);
+        let clipped_end = end.min(range_end);
+        let inside = if span_ms > 0 {
+            clipped_start < clipped_end
+        } else {
+            start >= range_start && start < range_end
+        };
+        if !inside {
+            return None;
+        }
+        Some(ClippedSession {
+            start: clipped_start,
+            end: clipped_end.max(clipped_start),
+            span_ms,
+            duration_seconds: prorate(duration_seconds, (clipped_end - clipped_start).num_milliseconds().max(0), span_ms),
+        })
+    }
+
+    /// Focus seconds per local day, split proportionally at midnight.
+    pub fn seconds_by_day(&self, zone: Zone) -> Vec<(NaiveDate, i64)> {
+        if self.span_ms <= 0 || self.start == self.end {
+            return vec![(zone.local_date(self.start), self.duration_seconds)];
+        }
+        let clipped_ms = (self.end - self.start).num_milliseconds();
+        split_by_day(zone, self.start, self.end)
+            .into_iter()
+            .map(|(date, ms)| (date, prorate(self.duration_seconds, ms, clipped_ms)))
+            .collect()
+    }
+}
+
+/// Running totals for one task or task group.
+#[derive(Debug, Default, Clone)]
+pub struct Tally {
+    pub total_sessions: i32,
+    pub total_duration_seconds: i64,
+    pub completed_sessions: i32,
+    pub abandoned_sessions: i32,
+    pub interrupted_sessions: i32,
+    pub auto_closed_sessions: i32,
+    pub manual_sessions: i32,
+}
+
+impl Tally {
+    pub fn add(&mut self, outcome: Option<SessionOutcome>, is_manual: bool, duration_seconds: i64) {
+        self.total_sessions += 1;
+        self.total_duration_seconds += duration_seconds;
+        match outcome {
+            Some(SessionOutcome::Completed) => self.completed_sessions += 1,
+            Some(SessionOutcome::Abandoned) => self.abandoned_sessions += 1,
+            Some(SessionOutcome::Interrupted) => self.interrupted_sessions += 1,
+            Some(SessionOutcome::AutoClosed) => self.auto_closed_sessions += 1,
+            None => {}
+        }
+        if is_manual {
+            self.manual_sessions += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: &Tally) {
+        self.total_sessions += other.total_sessions;
+        self.total_duration_seconds += other.total_duration_seconds;
+        self.completed_sessions += other.completed_sessions;
+        self.abandoned_sessions += other.abandoned_sessions;
+        self.interrupted_sessions += other.interrupted_sessions;
+        self.auto_closed_sessions += other.auto_closed_sessions;
+        self.manual_sessions += other.manual_sessions;
+    }
+}
+
+// 周以周一开始，与前端统计的 weekStartsOn: 1 保持一致
+pub fn bucket_start(date: NaiveDate, bucket: TimeBucket) -> NaiveDate {
+    match bucket {
+        TimeBucket::Day => date,
+        TimeBucket::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+        TimeBucket::Month => date.with_day(1).unwrap(),
+    }
+}
+
+pub fn next_bucket(start: NaiveDate, bucket: TimeBucket) -> NaiveDate {
+    match bucket {
+        TimeBucket::Day => start + Duration::days(1),
+        TimeBucket::Week => start + Duration::days(7),
+        TimeBucket::Month => start + Months::new(1),
+    }
+}
+
+/// Every bucket start from the bucket containing `first` up to the one containing `last`.
+pub fn bucket_range(first: NaiveDate, last: NaiveDate, bucket: TimeBucket) -> Vec<NaiveDate> {
+    let mut buckets = Vec::new();
+    let mut current = bucket_start(first, bucket);
+    while current <= last {
+        buckets.push(current);
+        current = next_bucket(current, bucket);
+    }
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn zone(name: &str) -> Zone {
+        Zone::resolve(Some(name)).unwrap()
+    }
+
+    #[test]
+    fn cross_midnight_session_splits_by_local_day() {
+        // 上海 23:00 - 次日 01:00，中间暂停了一小时
+        let session = ClippedSession::clip(
+            utc("2025-01-01T15:00:00Z"),
+            utc("2025-01-01T17:00:00Z"),
+            3600,
+            utc("2024-12-31T16:00:00Z"),
+            utc("2025-01-02T16:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(session.duration_seconds, 3600);
+        assert_eq!(
+            session.seconds_by_day(zone("Asia/Shanghai")),
+            vec![(date("2025-01-01"), 1800), (date("2025-01-02"), 1800)]
+        );
+        // 同一会话在 UTC 下不跨天
+        assert_eq!(session.seconds_by_day(zone("UTC")), vec![(date("2025-01-01"), 3600)]);
+    }
+
+    #[test]
+    fn session_is_clipped_to_range() {
+        let start = utc("2025-01-01T10:00:00Z");
+        let end = utc("2025-01-01T12:00:00Z");
+        let session = ClippedSession::clip(start, end, 7200, utc("2025-01-01T11:00:00Z"), utc("2025-01-02T00:00:00Z")).unwrap();
+        assert_eq!((session.start, session.end), (utc("2025-01-01T11:00:00Z"), end));
+        assert_eq!(session.span_ms, 7_200_000);
+        assert_eq!(session.duration_seconds, 3600);
+
+        assert!(ClippedSession::clip(start, end, 7200, end, utc("2025-01-02T00:00:00Z")).is_none());
+    }
+
+    #[test]
+    fn zero_span_session_counts_on_its_start_day() {
+        let at = utc("2025-01-01T23:30:00Z");
+        let session = ClippedSession::clip(at, at, 600, utc("2025-01-01T00:00:00Z"), utc("2025-01-02T00:00:00Z")).unwrap();
+        assert_eq!(session.duration_seconds, 600);
+        assert_eq!(session.seconds_by_day(zone("Asia/Shanghai")), vec![(date("2025-01-02"), 600)]);
+
+        assert!(ClippedSession::clip(at, at, 600, utc("2025-01-02T00:00:00Z"), utc("2025-01-03T00:00:00Z")).is_none());
+    }
+
+    #[test]
+    fn dst_days_have_their_real_length() {
+        // 纽约 2025-03-09 02:00 拨快一小时，当天只有 23 小时
+        let new_york = zone("America/New_York");
+        let start = new_york.start_of_day(date("2025-03-09"));
+        let end = new_york.start_of_day(date("2025-03-10"));
+        assert_eq!((start, end), (utc("2025-03-09T05:00:00Z"), utc("2025-03-10T04:00:00Z")));
+        assert_eq!(split_by_day(new_york, start, end), vec![(date("2025-03-09"), 23 * 3_600_000)]);
+        assert_eq!(
+            parse_range("2025-03-09", "2025-03-09", new_york).unwrap(),
+            (start, end)
+        );
+    }
+
+    #[test]
+    fn skipped_midnight_rolls_forward() {
+        // 圣保罗 2018-11-04 的午夜直接跳到 01:00
+        let sao_paulo = zone("America/Sao_Paulo");
+        let start = sao_paulo.start_of_day(date("2018-11-04"));
+        assert_eq!(start, utc("2018-11-04T03:00:00Z"));
+
+        let session = ClippedSession::clip(
+            utc("2018-11-04T02:00:00Z"),
+            utc("2018-11-04T04:00:00Z"),
+            7200,
+            utc("2018-11-03T00:00:00Z"),
+            utc("2018-11-05T00:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(
+            session.seconds_by_day(sao_paulo),
+            vec![(date("2018-11-03"), 3600), (date("2018-11-04"), 3600)]
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use crate::database::Database;
+use crate::error::AppResult;
+use crate::models::*;
+
+pub const TICK_EVENT: &str = "timer://tick";
+pub const FINISHED_EVENT: &str = "timer://finished";
+
+pub enum TimerEvent {
+    Tick(TimerSnapshot),
+    Finished(TaskSession),
+}
+
+/// Authoritative countdown/forward timer. Owns a copy of the active session
+/// that is kept in sync with the database after every session command.
+#[derive(Default)]
+pub struct TimerService {
+    active: Option<ActiveSession>,
+}
+
+impl TimerService {
+    pub fn sync(&mut self, db: &Database) -> AppResult<()> {
+        self.active = db.get_active_session()?;
+        Ok(())
+    }
+
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Option<TimerSnapshot> {
+        self.active.as_ref().map(|active| {
+            let elapsed_ms = active.session.focused_ms(now);
+            TimerSnapshot {
+                session_id: active.session.id.clone(),
+                task_id: active.task.id.clone(),
+                kind: active.session.kind,
+                elapsed_ms,
+                remaining_ms: active
+                    .session
+                    .planned_minutes
+                    .map(|minutes| (minutes as i64 * 60_000 - elapsed_ms).max(0)),
+                is_paused: active.session.is_paused,
+            }
+        })
+    }
+
+    /// Advances the timer, completing countdown sessions whose time is up.
+    pub fn tick(&mut self, db: &Database, now: DateTime<Utc>) -> AppResult<Option<TimerEvent>> {
+        let snapshot = match self.snapshot(now) {
+            Some(snapshot) => snapshot,
+            None => return Ok(None),
+        };
+
+        if snapshot.remaining_ms != Some(0) || snapshot.is_paused {
+            return Ok(Some(TimerEvent::Tick(snapshot)));
+        }
+
+        let session = db.end_session(EndSessionRequest {
+            session_id: snapshot.session_id,
+            duration_minutes: None,
+            outcome: Some(SessionOutcome::Completed),
+        })?;
+        self.active = None;
+
+        println!("Countdown finished, session completed: {}", session.id);
+
+        Ok(Some(TimerEvent::Finished(session)))
+    }
+}
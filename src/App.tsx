@@ -6,7 +6,8 @@ import { Timer } from './components/Timer';
 import { Statistics } from './components/Statistics';
 import { Settings as SettingsComponent } from './components/Settings';
 import { ApiService } from './services/api';
-import type { TaskGroup, Task, ActiveSession, BackgroundSettings } from './types';
+import { getErrorMessage } from './utils/helpers';
+import type { TaskGroup, Task, TaskSession, ActiveSession, BackgroundSettings } from './types';
 import { DEFAULT_BACKGROUND_SETTINGS } from './types';
 import { LanguageProvider, useLanguage } from './i18n/LanguageProvider';
 import './App.css';
@@ -50,9 +51,12 @@ function AppMain() {
       const session = await ApiService.getActiveSession();
       console.log('Active session result:', session);
       setActiveSession(session);
+      if (session) {
+        applyPauseState(session.session);
+      }
       console.log('Active session state updated');
     } catch (error) {
-      console.error('Failed to load active session:', error);
+      console.error('Failed to load active session:', getErrorMessage(error));
     }
   };
 
@@ -63,13 +67,15 @@ function AppMain() {
   const handleStartTask = async (task: Task) => {
     console.log('Starting task:', task);
     try {
-      const session = await ApiService.startSession({ task_id: task.id });
+      // 已有进行中的会话时切换到新任务，上一个会话记为中断
+      const session = await ApiService.startSession({ task_id: task.id, on_active: 'Switch' });
       console.log('Session started:', session);
       await loadActiveSession();
       console.log('Active session loaded, switching to timer tab');
       setActiveTab('timer');
     } catch (error) {
-      console.error('Failed to start task:', error);
+      console.error('Failed to start task:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -89,19 +95,24 @@ function AppMain() {
     console.log('Session end handling completed');
   };
 
-  const handlePause = () => {
-    if (isPaused) {
-      // Resume timer
-      setIsPaused(false);
-      if (pauseStartTime) {
-        const pauseDuration = Date.now() - pauseStartTime;
-        setTotalPausedTime(prev => prev + pauseDuration);
-        setPauseStartTime(null);
-      }
-    } else {
-      // Pause timer
-      setIsPaused(true);
-      setPauseStartTime(Date.now());
+  const applyPauseState = (session: TaskSession) => {
+    setIsPaused(session.is_paused);
+    setPauseStartTime(session.paused_at ? new Date(session.paused_at).getTime() : null);
+    setTotalPausedTime(session.total_paused_duration_ms);
+  };
+
+  const handlePause = async () => {
+    if (!activeSession) return;
+
+    try {
+      // 暂停状态由后端持久化，重启后可以恢复
+      const session = isPaused
+        ? await ApiService.resumeSession({ session_id: activeSession.session.id })
+        : await ApiService.pauseSession({ session_id: activeSession.session.id });
+      applyPauseState(session);
+    } catch (error) {
+      console.error('Failed to toggle pause:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -112,7 +123,7 @@ function AppMain() {
       // 结束当前会话
       await ApiService.endSession({
         session_id: activeSession.session.id,
-        duration_minutes: undefined,
+        outcome: 'Interrupted',
       });
       
       // 重新开始会话
@@ -126,7 +137,8 @@ function AppMain() {
       setPauseStartTime(null);
       setTotalPausedTime(0);
     } catch (error) {
-      console.error('Failed to reset session:', error);
+      console.error('Failed to reset session:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
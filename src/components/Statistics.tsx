@@ -2,7 +2,7 @@ import { useState, useEffect } from 'react';
 import { Calendar, BarChart3, TrendingUp, Clock } from 'lucide-react';
 import { ApiService } from '../services/api';
 import type { StatisticsRequest, StatisticsResponse, StatisticsPeriod } from '../types';
-import { formatDuration, getDateRange, formatDate, calculateCompletionRate } from '../utils/helpers';
+import { formatDuration, getDateRange, formatDate, calculateCompletionRate, getErrorMessage } from '../utils/helpers';
 import { useLanguage } from '../i18n/LanguageProvider';
 
 export function Statistics() {
@@ -65,10 +65,12 @@ export function Statistics() {
         endDate = range.end;
       }
 
+      // 以本地日期加时区发送，由后端按本地自然日划分统计区间
       const request: StatisticsRequest = {
-        start_date: startDate.toISOString(),
-        end_date: endDate.toISOString(),
+        start_date: formatDate(startDate),
+        end_date: formatDate(endDate),
         group_by: groupBy,
+        timezone: Intl.DateTimeFormat().resolvedOptions().timeZone,
       };
 
       console.log('Loading statistics with request:', request);
@@ -76,7 +78,8 @@ export function Statistics() {
       console.log('Statistics loaded:', data);
       setStatistics(data);
     } catch (error) {
-      console.error('Failed to load statistics:', error);
+      console.error('Failed to load statistics:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     } finally {
       setLoading(false);
     }
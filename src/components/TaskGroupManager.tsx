@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react';
 import { Plus, Edit2, Trash2, FolderOpen } from 'lucide-react';
 import { ApiService } from '../services/api';
+import { getErrorMessage } from '../utils/helpers';
 import type { TaskGroup, CreateTaskGroupRequest, UpdateTaskGroupRequest } from '../types';
 import { useLanguage } from '../i18n/LanguageProvider';
 
@@ -25,7 +26,7 @@ export function TaskGroupManager({ onSelectGroup, selectedGroupId }: TaskGroupMa
       const data = await ApiService.getTaskGroups();
       setGroups(data);
     } catch (error) {
-      console.error('Failed to load task groups:', error);
+      console.error('Failed to load task groups:', getErrorMessage(error));
     }
   };
 
@@ -42,7 +43,8 @@ export function TaskGroupManager({ onSelectGroup, selectedGroupId }: TaskGroupMa
       setFormData({ name: '', description: '' });
       setIsCreating(false);
     } catch (error) {
-      console.error('Failed to create task group:', error);
+      console.error('Failed to create task group:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -52,28 +54,30 @@ export function TaskGroupManager({ onSelectGroup, selectedGroupId }: TaskGroupMa
     try {
       const request: UpdateTaskGroupRequest = {
         name: formData.name.trim(),
-        description: formData.description.trim() || undefined,
+        description: formData.description.trim() || null,
       };
       const updatedGroup = await ApiService.updateTaskGroup(editingGroup.id, request);
       setGroups(groups.map(g => g.id === updatedGroup.id ? updatedGroup : g));
       setEditingGroup(null);
       setFormData({ name: '', description: '' });
     } catch (error) {
-      console.error('Failed to update task group:', error);
+      console.error('Failed to update task group:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
   const handleDelete = async (id: string) => {
-    if (!confirm('确定要删除这个任务组吗？这将删除组内的所有任务。')) return;
+    if (!confirm('确定要把这个任务组移到回收站吗？之后可以从回收站恢复。')) return;
 
     try {
-      await ApiService.deleteTaskGroup(id);
+      await ApiService.trashTaskGroup(id);
       setGroups(groups.filter(g => g.id !== id));
       if (selectedGroupId === id) {
         onSelectGroup(groups[0]);
       }
     } catch (error) {
-      console.error('Failed to delete task group:', error);
+      console.error('Failed to delete task group:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
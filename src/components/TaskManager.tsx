@@ -1,6 +1,7 @@
 import { useState, useEffect } from 'react';
 import { Plus, Edit2, Trash2, Play, Clock } from 'lucide-react';
 import { ApiService } from '../services/api';
+import { getErrorMessage } from '../utils/helpers';
 import type { Task, TaskGroup, CreateTaskRequest, UpdateTaskRequest } from '../types';
 import { TIMER_PRESETS, getTimerPresets } from '../types';
 import { useLanguage } from '../i18n/LanguageProvider';
@@ -42,7 +43,7 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
       const data = await ApiService.getTasksByGroup(selectedGroup.id);
       setTasks(data);
     } catch (error) {
-      console.error('Failed to load tasks:', error);
+      console.error('Failed to load tasks:', getErrorMessage(error));
     }
   };
 
@@ -61,6 +62,7 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
         name: formData.name.trim(),
         description: formData.description.trim() || undefined,
         duration_minutes: duration,
+        timer_mode: formData.isForwardTiming ? 'Forward' : undefined,
       };
       const newTask = await ApiService.createTask(request);
       setTasks([newTask, ...tasks]);
@@ -74,7 +76,8 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
       });
       setIsCreating(false);
     } catch (error) {
-      console.error('Failed to create task:', error);
+      console.error('Failed to create task:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -96,10 +99,11 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
         finalDuration: duration
       });
 
+      // 省略的字段不会被修改，清空描述或切换为正向计时需要显式传 null
       const request: UpdateTaskRequest = {
         name: formData.name.trim(),
-        description: formData.description.trim() || undefined,
-        duration_minutes: duration,
+        description: formData.description.trim() || null,
+        duration_minutes: duration ?? null,
       };
       const updatedTask = await ApiService.updateTask(editingTask.id, request);
       console.log('Updated task:', updatedTask);
@@ -114,7 +118,8 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
         isForwardTiming: false,
       });
     } catch (error) {
-      console.error('Failed to update task:', error);
+      console.error('Failed to update task:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -122,10 +127,11 @@ export function TaskManager({ selectedGroup, onStartTask }: TaskManagerProps) {
     if (!confirm(t.confirmDelete)) return;
 
     try {
-      await ApiService.deleteTask(id);
+      await ApiService.trashTask(id);
       setTasks(tasks.filter(t => t.id !== id));
     } catch (error) {
-      console.error('Failed to delete task:', error);
+      console.error('Failed to delete task:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -1,8 +1,9 @@
-import { useState, useEffect, useRef } from 'react';
+import { useState, useEffect } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { Play, Pause, Square, RotateCcw } from 'lucide-react';
 import { ApiService } from '../services/api';
-import type { ActiveSession, EndSessionRequest } from '../types';
-import { formatTime } from '../utils/helpers';
+import type { ActiveSession, EndSessionRequest, TaskSession, TimerSnapshot } from '../types';
+import { formatTime, getErrorMessage } from '../utils/helpers';
 import { useLanguage } from '../i18n/LanguageProvider';
 
 interface TimerProps {
@@ -28,11 +29,11 @@ export function Timer({
   const [timeLeft, setTimeLeft] = useState(0);
   const [elapsedTime, setElapsedTime] = useState(0);
   const [isRunning, setIsRunning] = useState(false);
-  const intervalRef = useRef<number | null>(null);
 
   useEffect(() => {
     if (activeSession) {
-      const task = activeSession.task;
+      // 番茄钟的休息阶段有各自的时长，以会话的计划时长为准
+      const plannedMinutes = activeSession.session.planned_minutes;
       
       // 立即计算当前真实时间，避免闪烁
       const now = Date.now();
@@ -40,9 +41,9 @@ export function Timer({
       const currentPauseTime = pauseStartTime ? now - pauseStartTime : 0;
       const actualElapsed = Math.floor((now - sessionStartTime - totalPausedTime - currentPauseTime) / 1000);
       
-      if (task.duration_minutes != null) {
+      if (plannedMinutes != null) {
         // Countdown timer
-        const remaining = Math.max(0, task.duration_minutes * 60 - actualElapsed);
+        const remaining = Math.max(0, plannedMinutes * 60 - actualElapsed);
         setTimeLeft(remaining);
         setElapsedTime(actualElapsed);
       } else {
@@ -63,59 +64,32 @@ export function Timer({
       setIsRunning(false);
       setTimeLeft(0);
       setElapsedTime(0);
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current);
-        intervalRef.current = null;
-      }
     }
   }, [activeSession, pauseStartTime, totalPausedTime]);
 
+  // 计时由后端驱动：tick 事件更新显示，倒计时结束由后端自动完成会话
   useEffect(() => {
-    if (isRunning && !isPaused) {
-      intervalRef.current = setInterval(() => {
-        const now = Date.now();
-        const sessionStartTime = new Date(activeSession!.session.start_time).getTime();
-        // 计算当前暂停时间（如果正在暂停）
-        const currentPauseTime = pauseStartTime ? now - pauseStartTime : 0;
-        // 实际经过的时间 = 当前时间 - 开始时间 - 总暂停时间 - 当前暂停时间
-        const actualElapsed = Math.floor((now - sessionStartTime - totalPausedTime - currentPauseTime) / 1000);
-        
-        if (activeSession!.task.duration_minutes != null) {
-          // Countdown timer
-          const remaining = Math.max(0, activeSession!.task.duration_minutes * 60 - actualElapsed);
-          setTimeLeft(remaining);
-          setElapsedTime(actualElapsed);
-          
-          if (remaining === 0) {
-            handleComplete();
-          }
-        } else {
-          // Forward timer
-          setTimeLeft(0); // 正向计时不需要倒计时
-          setElapsedTime(actualElapsed);
-          console.log('Forward timer update:', {
-            actualElapsed,
-            sessionStartTime: new Date(sessionStartTime).toLocaleString(),
-            now: new Date(now).toLocaleString(),
-            totalPausedTime,
-            currentPauseTime
-          });
-        }
-      }, 1000);
-    } else {
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current);
-        intervalRef.current = null;
-      }
-    }
+    if (!activeSession) return;
+    const sessionId = activeSession.session.id;
+
+    const unlistenTick = listen<TimerSnapshot>('timer://tick', (event) => {
+      const snapshot = event.payload;
+      if (snapshot.session_id !== sessionId) return;
+      setElapsedTime(Math.floor(snapshot.elapsed_ms / 1000));
+      setTimeLeft(snapshot.remaining_ms != null ? Math.ceil(snapshot.remaining_ms / 1000) : 0);
+    });
+
+    const unlistenFinished = listen<TaskSession>('timer://finished', (event) => {
+      if (event.payload.id !== sessionId) return;
+      console.log('Session completed by backend timer:', sessionId);
+      onSessionEnd();
+    });
 
     return () => {
-      if (intervalRef.current) {
-        clearInterval(intervalRef.current);
-        intervalRef.current = null;
-      }
+      unlistenTick.then((unlisten) => unlisten());
+      unlistenFinished.then((unlisten) => unlisten());
     };
-  }, [isRunning, isPaused, activeSession, pauseStartTime, totalPausedTime]);
+  }, [activeSession]);
 
   const handlePause = () => {
     onPause();
@@ -126,24 +100,12 @@ export function Timer({
 
     console.log('Stopping session:', activeSession.session.id);
     console.log('Elapsed time (seconds):', elapsedTime);
-    console.log('Task type:', activeSession.task.duration_minutes != null ? 'Countdown' : 'Forward');
+    console.log('Task type:', activeSession.session.planned_minutes != null ? 'Countdown' : 'Forward');
 
     try {
-      let duration: number | undefined;
-      
-      if (activeSession.task.duration_minutes != null) {
-        // 倒计时任务：记录实际经过的时间
-        duration = Math.floor(elapsedTime / 60);
-        console.log('Countdown task - recording actual elapsed time:', duration, 'minutes');
-      } else {
-        // 正向计时任务：记录实际经过的时间
-        duration = Math.floor(elapsedTime / 60);
-        console.log('Forward task - recording actual elapsed time:', duration, 'minutes');
-      }
-
+      // 时长由后端根据开始、结束和暂停时间计算
       const request: EndSessionRequest = {
         session_id: activeSession.session.id,
-        duration_minutes: duration > 0 ? duration : 1, // 至少记录1分钟
       };
 
       console.log('Ending session with request:', request);
@@ -152,42 +114,8 @@ export function Timer({
       console.log('Calling onSessionEnd to clear active session state');
       onSessionEnd();
     } catch (error) {
-      console.error('Failed to end session:', error);
-    }
-  };
-
-  const handleComplete = async () => {
-    if (!activeSession) return;
-
-    console.log('Completing session:', activeSession.session.id);
-    console.log('Elapsed time (seconds):', elapsedTime);
-    console.log('Task type:', activeSession.task.duration_minutes != null ? 'Countdown' : 'Forward');
-
-    try {
-      let duration: number;
-      
-      if (activeSession.task.duration_minutes != null) {
-        // 倒计时任务：记录预设的完整时间
-        duration = activeSession.task.duration_minutes;
-        console.log('Countdown task - recording full duration:', duration, 'minutes');
-      } else {
-        // 正向计时任务：记录实际经过的时间
-        duration = Math.floor(elapsedTime / 60);
-        console.log('Forward task - recording actual elapsed time:', duration, 'minutes');
-      }
-
-      const request: EndSessionRequest = {
-        session_id: activeSession.session.id,
-        duration_minutes: duration > 0 ? duration : 1, // 至少记录1分钟
-      };
-
-      console.log('Completing session with request:', request);
-      await ApiService.endSession(request);
-      console.log('Session completed successfully - time recorded in statistics');
-      console.log('Calling onSessionEnd to clear active session state');
-      onSessionEnd();
-    } catch (error) {
-      console.error('Failed to complete session:', error);
+      console.error('Failed to end session:', getErrorMessage(error));
+      alert(getErrorMessage(error));
     }
   };
 
@@ -208,10 +136,11 @@ export function Timer({
     );
   }
 
-  const isCountdown = activeSession.task.duration_minutes != null;
+  const plannedMinutes = activeSession.session.planned_minutes;
+  const isCountdown = plannedMinutes != null;
   const displayTime = isCountdown ? timeLeft : elapsedTime;
-  const progress = isCountdown && activeSession.task.duration_minutes 
-    ? ((activeSession.task.duration_minutes * 60 - timeLeft) / (activeSession.task.duration_minutes * 60)) * 100 
+  const progress = isCountdown && plannedMinutes 
+    ? ((plannedMinutes * 60 - timeLeft) / (plannedMinutes * 60)) * 100 
     : 0;
 
   // 调试信息
@@ -222,7 +151,7 @@ export function Timer({
     timeLeft,
     isRunning,
     isPaused,
-    taskDuration: activeSession.task.duration_minutes
+    plannedMinutes
   });
 
   return (
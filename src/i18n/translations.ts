@@ -129,7 +129,7 @@ export const translations: Record<Language, Translations> = {
     update: '更新',
     cancel: '取消',
     delete: '删除',
-    confirmDelete: '确定要删除这个任务组吗？这将删除组内的所有任务。',
+    confirmDelete: '确定要把这个任务移到回收站吗？之后可以恢复。',
     noTaskGroups: '还没有任务组',
     createFirstTaskGroup: '点击上方按钮创建第一个任务组',
     
@@ -237,7 +237,7 @@ export const translations: Record<Language, Translations> = {
     update: 'Update',
     cancel: 'Cancel',
     delete: 'Delete',
-    confirmDelete: 'Are you sure you want to delete this task group? This will delete all tasks in the group.',
+    confirmDelete: 'Move this task to the trash? You can restore it later.',
     noTaskGroups: 'No task groups yet',
     createFirstTaskGroup: 'Click the button above to create your first task group',
     
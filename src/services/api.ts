@@ -1,17 +1,53 @@
 import { invoke } from '@tauri-apps/api/core';
 import type {
   TaskGroup,
+  TaskGroupNode,
   Task,
   TaskSession,
   ActiveSession,
+  TimerSnapshot,
+  PomodoroState,
+  SessionDetail,
+  ResolveRecoveredSessionRequest,
+  CreateManualSessionRequest,
+  UpdateSessionRequest,
+  ListSessionsRequest,
+  SessionPage,
   CreateTaskGroupRequest,
   UpdateTaskGroupRequest,
   CreateTaskRequest,
   UpdateTaskRequest,
   StartSessionRequest,
   EndSessionRequest,
+  PauseSessionRequest,
+  ResumeSessionRequest,
   StatisticsRequest,
   StatisticsResponse,
+  TimeSeriesRequest,
+  TimeSeriesResponse,
+  Trash,
+  Tag,
+  CreateTagRequest,
+  UpdateTagRequest,
+  SetTaskTagsRequest,
+  Subtask,
+  AddSubtaskRequest,
+  ReorderSubtasksRequest,
+  TaskFilter,
+  ReorderTasksRequest,
+  ReorderTaskGroupsRequest,
+  SetTaskStatusRequest,
+  DueTasksRequest,
+  DueTasks,
+  DueRoutines,
+  TaskEffort,
+  EstimateReportRequest,
+  EstimateReport,
+  Goal,
+  CreateGoalRequest,
+  UpdateGoalRequest,
+  GoalProgressRequest,
+  GoalProgress,
 } from '../types';
 
 export class ApiService {
@@ -20,16 +56,40 @@ export class ApiService {
     return await invoke('create_task_group', { request });
   }
 
-  static async getTaskGroups(): Promise<TaskGroup[]> {
-    return await invoke('get_task_groups');
+  static async getTaskGroups(includeArchived?: boolean): Promise<TaskGroup[]> {
+    return await invoke('get_task_groups', { includeArchived });
+  }
+
+  static async getTaskGroupTree(includeArchived?: boolean): Promise<TaskGroupNode[]> {
+    return await invoke('get_task_group_tree', { includeArchived });
   }
 
   static async updateTaskGroup(id: string, request: UpdateTaskGroupRequest): Promise<TaskGroup> {
     return await invoke('update_task_group', { id, request });
   }
 
-  static async deleteTaskGroup(id: string): Promise<void> {
-    return await invoke('delete_task_group', { id });
+  static async reorderTaskGroups(request: ReorderTaskGroupsRequest): Promise<TaskGroup[]> {
+    return await invoke('reorder_task_groups', { request });
+  }
+
+  static async archiveTaskGroup(id: string): Promise<TaskGroup> {
+    return await invoke('archive_task_group', { id });
+  }
+
+  static async unarchiveTaskGroup(id: string): Promise<TaskGroup> {
+    return await invoke('unarchive_task_group', { id });
+  }
+
+  static async trashTaskGroup(id: string): Promise<TaskGroup> {
+    return await invoke('trash_task_group', { id });
+  }
+
+  static async restoreTaskGroup(id: string): Promise<TaskGroup> {
+    return await invoke('restore_task_group', { id });
+  }
+
+  static async purgeTaskGroup(id: string): Promise<void> {
+    return await invoke('purge_task_group', { id });
   }
 
   // Task operations
@@ -37,16 +97,119 @@ export class ApiService {
     return await invoke('create_task', { request });
   }
 
-  static async getTasksByGroup(taskGroupId: string): Promise<Task[]> {
-    return await invoke('get_tasks_by_group', { taskGroupId });
+  static async getTasksByGroup(taskGroupId: string, filter?: TaskFilter): Promise<Task[]> {
+    return await invoke('get_tasks_by_group', { taskGroupId, filter });
+  }
+
+  static async reorderTasks(request: ReorderTasksRequest): Promise<Task[]> {
+    return await invoke('reorder_tasks', { request });
+  }
+
+  static async setTaskStatus(request: SetTaskStatusRequest): Promise<Task> {
+    return await invoke('set_task_status', { request });
+  }
+
+  static async getDueTasks(request: DueTasksRequest): Promise<DueTasks> {
+    return await invoke('get_due_tasks', { request });
+  }
+
+  static async getDueRoutines(request: DueTasksRequest): Promise<DueRoutines> {
+    return await invoke('get_due_routines', { request });
   }
 
   static async updateTask(id: string, request: UpdateTaskRequest): Promise<Task> {
     return await invoke('update_task', { id, request });
   }
 
-  static async deleteTask(id: string): Promise<void> {
-    return await invoke('delete_task', { id });
+  static async archiveTask(id: string): Promise<Task> {
+    return await invoke('archive_task', { id });
+  }
+
+  static async unarchiveTask(id: string): Promise<Task> {
+    return await invoke('unarchive_task', { id });
+  }
+
+  static async trashTask(id: string): Promise<Task> {
+    return await invoke('trash_task', { id });
+  }
+
+  static async restoreTask(id: string): Promise<Task> {
+    return await invoke('restore_task', { id });
+  }
+
+  static async purgeTask(id: string): Promise<void> {
+    return await invoke('purge_task', { id });
+  }
+
+  static async getTrash(): Promise<Trash> {
+    return await invoke('get_trash');
+  }
+
+  // Tag operations
+  static async createTag(request: CreateTagRequest): Promise<Tag> {
+    return await invoke('create_tag', { request });
+  }
+
+  static async getTags(): Promise<Tag[]> {
+    return await invoke('get_tags');
+  }
+
+  static async updateTag(id: string, request: UpdateTagRequest): Promise<Tag> {
+    return await invoke('update_tag', { id, request });
+  }
+
+  static async deleteTag(id: string): Promise<void> {
+    return await invoke('delete_tag', { id });
+  }
+
+  static async getTaskTags(taskId: string): Promise<Tag[]> {
+    return await invoke('get_task_tags', { taskId });
+  }
+
+  static async setTaskTags(request: SetTaskTagsRequest): Promise<Tag[]> {
+    return await invoke('set_task_tags', { request });
+  }
+
+  // Subtask operations
+  static async getSubtasks(taskId: string): Promise<Subtask[]> {
+    return await invoke('get_subtasks', { taskId });
+  }
+
+  static async addSubtask(request: AddSubtaskRequest): Promise<Subtask> {
+    return await invoke('add_subtask', { request });
+  }
+
+  static async reorderSubtasks(request: ReorderSubtasksRequest): Promise<Subtask[]> {
+    return await invoke('reorder_subtasks', { request });
+  }
+
+  static async toggleSubtask(id: string): Promise<Subtask> {
+    return await invoke('toggle_subtask', { id });
+  }
+
+  static async deleteSubtask(id: string): Promise<void> {
+    return await invoke('delete_subtask', { id });
+  }
+
+  // Goal operations
+  static async createGoal(request: CreateGoalRequest): Promise<Goal> {
+    return await invoke('create_goal', { request });
+  }
+
+  static async getGoals(): Promise<Goal[]> {
+    return await invoke('get_goals');
+  }
+
+  static async updateGoal(id: string, request: UpdateGoalRequest): Promise<Goal> {
+    return await invoke('update_goal', { id, request });
+  }
+
+  static async deleteGoal(id: string): Promise<void> {
+    return await invoke('delete_goal', { id });
+  }
+
+  static async getGoalProgress(request: GoalProgressRequest): Promise<GoalProgress[]> {
+    return await invoke('get_goal_progress', { request });
   }
 
   // Session operations
@@ -58,12 +221,68 @@ export class ApiService {
     return await invoke('end_session', { request });
   }
 
+  static async pauseSession(request: PauseSessionRequest): Promise<TaskSession> {
+    return await invoke('pause_session', { request });
+  }
+
+  static async resumeSession(request: ResumeSessionRequest): Promise<TaskSession> {
+    return await invoke('resume_session', { request });
+  }
+
   static async getActiveSession(): Promise<ActiveSession | null> {
     return await invoke('get_active_session');
   }
 
+  static async getTimerSnapshot(): Promise<TimerSnapshot | null> {
+    return await invoke('get_timer_snapshot');
+  }
+
+  static async createManualSession(request: CreateManualSessionRequest): Promise<TaskSession> {
+    return await invoke('create_manual_session', { request });
+  }
+
+  static async updateSession(id: string, request: UpdateSessionRequest): Promise<TaskSession> {
+    return await invoke('update_session', { id, request });
+  }
+
+  static async deleteSession(id: string): Promise<void> {
+    return await invoke('delete_session', { id });
+  }
+
+  static async listSessions(request: ListSessionsRequest): Promise<SessionPage> {
+    return await invoke('list_sessions', { request });
+  }
+
+  static async getRecoveredSessions(): Promise<SessionDetail[]> {
+    return await invoke('get_recovered_sessions');
+  }
+
+  static async resolveRecoveredSession(request: ResolveRecoveredSessionRequest): Promise<TaskSession> {
+    return await invoke('resolve_recovered_session', { request });
+  }
+
+  static async getPomodoroState(taskId: string): Promise<PomodoroState> {
+    return await invoke('get_pomodoro_state', { taskId });
+  }
+
+  static async resetPomodoroCycle(taskId: string): Promise<PomodoroState> {
+    return await invoke('reset_pomodoro_cycle', { taskId });
+  }
+
   // Statistics operations
   static async getStatistics(request: StatisticsRequest): Promise<StatisticsResponse> {
     return await invoke('get_statistics', { request });
   }
+
+  static async getTimeSeries(request: TimeSeriesRequest): Promise<TimeSeriesResponse> {
+    return await invoke('get_time_series', { request });
+  }
+
+  static async getTaskEffort(taskId: string): Promise<TaskEffort> {
+    return await invoke('get_task_effort', { taskId });
+  }
+
+  static async getEstimateReport(request: EstimateReportRequest): Promise<EstimateReport> {
+    return await invoke('get_estimate_report', { request });
+  }
 }
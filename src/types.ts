@@ -4,6 +4,22 @@ export interface TaskGroup {
   description?: string;
   created_at: string;
   updated_at: string;
+  archived_at?: string;
+  deleted_at?: string; // 在回收站中
+  sort_order: number;
+  parent_id?: string;
+  color?: string; // CSS 颜色
+  icon?: string;
+  default_duration_minutes?: number;
+  default_timer_mode?: GroupTimerMode;
+}
+
+// 新建任务未指定时长时沿用任务组的默认计时方式
+export type GroupTimerMode = 'Countdown' | 'Forward';
+
+export interface TaskGroupNode {
+  task_group: TaskGroup;
+  children: TaskGroupNode[];
 }
 
 export interface Task {
@@ -12,17 +28,145 @@ export interface Task {
   name: string;
   description?: string;
   duration_minutes?: number;
+  pomodoro?: PomodoroConfig;
   created_at: string;
   updated_at: string;
+  archived_at?: string;
+  deleted_at?: string;
+  status: TaskStatus;
+  priority: TaskPriority;
+  due_date?: string; // YYYY-MM-DD
+  completed_at?: string;
+  estimate_minutes?: number; // 预计总专注时长，与计时器时长无关
+  recurrence?: Recurrence;
+  last_occurrence_date?: string;
+  sort_order: number;
+}
+
+export type RecurrenceRule = 'Daily' | 'Weekdays' | 'Weekly' | 'EveryNDays' | 'Monthly';
+
+export type Weekday = 'Mon' | 'Tue' | 'Wed' | 'Thu' | 'Fri' | 'Sat' | 'Sun';
+
+export interface Recurrence {
+  rule: RecurrenceRule;
+  days_of_week?: Weekday[]; // 仅 Weekly
+  interval_days?: number; // 仅 EveryNDays
+  day_of_month?: number; // 仅 Monthly，较短的月份取最后一天
+  start_date?: string;
+  target_minutes?: number; // 每次的目标专注时长，默认取 duration_minutes
+}
+
+export interface RoutineProgress {
+  task: Task;
+  task_group_name: string;
+  target_minutes?: number;
+  focused_minutes: number;
+  focused_seconds: number;
+  target_met: boolean;
+}
+
+export interface DueRoutines {
+  date: string;
+  routines: RoutineProgress[];
+}
+
+export type TaskStatus = 'Todo' | 'InProgress' | 'Done';
+
+export type TaskPriority = 'Low' | 'Medium' | 'High';
+
+export type TaskSortBy = 'Manual' | 'CreatedAt' | 'Priority' | 'DueDate' | 'Status';
+
+export interface TaskFilter {
+  include_archived?: boolean;
+  tag_id?: string;
+  statuses?: TaskStatus[];
+  priority?: TaskPriority;
+  sort_by?: TaskSortBy;
+}
+
+export interface DueTasksRequest {
+  timezone?: string;
+}
+
+export interface DueTasks {
+  date: string;
+  overdue: Task[];
+  due_today: Task[];
 }
 
+export interface Trash {
+  task_groups: TaskGroup[];
+  tasks: Task[];
+}
+
+export interface Tag {
+  id: string;
+  name: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface Subtask {
+  id: string;
+  task_id: string;
+  title: string;
+  position: number;
+  completed: boolean;
+  completed_at?: string;
+  created_at: string;
+  updated_at: string;
+}
+
+export type GoalScope = 'Task' | 'TaskGroup' | 'Tag'; // TaskGroup 包含子任务组
+
+export type GoalPeriod = 'Day' | 'Week'; // 周从周一开始
+
+export interface Goal {
+  id: string;
+  name: string;
+  scope: GoalScope;
+  scope_id: string;
+  period: GoalPeriod;
+  days_of_week?: Weekday[]; // 仅每日目标；不设置表示每天
+  target_minutes: number;
+  created_at: string;
+  updated_at: string;
+  timezone?: string; // 按该时区划分日和周，默认为系统时区
+}
+
+export interface ChecklistProgress {
+  total_items: number;
+  completed_items: number;
+  progress: number; // 0 - 1
+}
+
+export interface PomodoroConfig {
+  work_minutes: number;
+  short_break_minutes: number;
+  long_break_minutes: number;
+  long_break_every: number;
+}
+
+export type SessionKind = 'Focus' | 'ShortBreak' | 'LongBreak';
+
+export type SessionOutcome = 'Completed' | 'Abandoned' | 'Interrupted' | 'AutoClosed';
+
 export interface TaskSession {
   id: string;
   task_id: string;
   start_time: string;
   end_time?: string;
   duration_minutes?: number;
+  duration_seconds?: number;
   completed: boolean;
+  outcome?: SessionOutcome;
+  kind: SessionKind;
+  planned_minutes?: number;
+  is_paused: boolean;
+  paused_at?: string;
+  total_paused_duration_ms: number;
+  needs_review: boolean;
+  is_manual: boolean;
   created_at: string;
 }
 
@@ -30,16 +174,43 @@ export interface ActiveSession {
   session: TaskSession;
   task: Task;
   task_group: TaskGroup;
+  checklist: ChecklistProgress;
+}
+
+export interface SessionDetail {
+  session: TaskSession;
+  task: Task;
+  task_group: TaskGroup;
+}
+
+export interface TimerSnapshot {
+  session_id: string;
+  task_id: string;
+  kind: SessionKind;
+  elapsed_ms: number;
+  remaining_ms?: number;
+  is_paused: boolean;
 }
 
 export interface CreateTaskGroupRequest {
   name: string;
   description?: string;
+  parent_id?: string;
+  color?: string;
+  icon?: string;
+  default_duration_minutes?: number;
+  default_timer_mode?: GroupTimerMode;
 }
 
+// 更新请求中省略的字段保持不变，传 null 表示清空
 export interface UpdateTaskGroupRequest {
   name?: string;
-  description?: string;
+  description?: string | null;
+  parent_id?: string | null; // null 移到顶层
+  color?: string | null;
+  icon?: string | null;
+  default_duration_minutes?: number | null;
+  default_timer_mode?: GroupTimerMode | null;
 }
 
 export interface CreateTaskRequest {
@@ -47,52 +218,345 @@ export interface CreateTaskRequest {
   name: string;
   description?: string;
   duration_minutes?: number;
+  timer_mode?: GroupTimerMode; // 'Forward' 时不继承任务组的默认时长
+  pomodoro?: PomodoroConfig;
+  priority?: TaskPriority;
+  due_date?: string;
+  estimate_minutes?: number;
+  recurrence?: Recurrence;
 }
 
 export interface UpdateTaskRequest {
+  task_group_id?: string; // 移动到其他任务组，会话随任务一起移动
   name?: string;
-  description?: string;
-  duration_minutes?: number;
+  description?: string | null;
+  duration_minutes?: number | null; // null switches to a forward timer
+  pomodoro?: PomodoroConfig | null;
+  priority?: TaskPriority;
+  due_date?: string | null;
+  estimate_minutes?: number | null;
+  recurrence?: Recurrence | null;
+}
+
+// 列出的项按顺序排在最前，未列出的项保持原有相对顺序
+export interface ReorderTasksRequest {
+  task_group_id: string;
+  task_ids: string[];
+}
+
+export interface ReorderTaskGroupsRequest {
+  task_group_ids: string[];
+}
+
+export interface SetTaskStatusRequest {
+  task_id: string;
+  status: TaskStatus;
+}
+
+export type ActiveSessionPolicy = 'Reject' | 'Switch';
+
+export interface CreateTagRequest {
+  name: string;
+}
+
+export interface UpdateTagRequest {
+  name: string;
+}
+
+export interface SetTaskTagsRequest {
+  task_id: string;
+  tag_ids: string[]; // 替换任务现有的标签
+}
+
+export interface AddSubtaskRequest {
+  task_id: string;
+  title: string;
+}
+
+export interface ReorderSubtasksRequest {
+  task_id: string;
+  subtask_ids: string[]; // 任务的全部清单项，按新顺序排列
+}
+
+export interface CreateGoalRequest {
+  name: string;
+  scope: GoalScope;
+  scope_id: string;
+  period: GoalPeriod;
+  days_of_week?: Weekday[];
+  target_minutes: number;
+  timezone?: string;
+}
+
+// 目标的关联对象不能修改
+export interface UpdateGoalRequest {
+  name?: string;
+  period?: GoalPeriod;
+  days_of_week?: Weekday[] | null; // null 表示每天
+  target_minutes?: number;
+  timezone?: string | null; // null 表示使用系统时区
 }
 
 export interface StartSessionRequest {
   task_id: string;
+  kind?: SessionKind;
+  on_active?: ActiveSessionPolicy;
 }
 
 export interface EndSessionRequest {
   session_id: string;
   duration_minutes?: number;
+  outcome?: SessionOutcome;
+}
+
+export interface PauseSessionRequest {
+  session_id: string;
+}
+
+export interface ResumeSessionRequest {
+  session_id: string;
+}
+
+export interface CreateManualSessionRequest {
+  task_id: string;
+  start_time: string;
+  end_time: string;
+  duration_minutes?: number;
+  outcome?: SessionOutcome;
+}
+
+export interface UpdateSessionRequest {
+  task_id?: string;
+  start_time?: string;
+  end_time?: string;
+  duration_minutes?: number;
+  outcome?: SessionOutcome;
+}
+
+export type SortDirection = 'Asc' | 'Desc';
+
+export interface ListSessionsRequest {
+  start_date?: string;
+  end_date?: string;
+  task_id?: string;
+  task_group_id?: string;
+  tag_id?: string;
+  outcome?: SessionOutcome;
+  sort?: SortDirection;
+  cursor?: string;
+  limit?: number;
+  timezone?: string; // 按该时区解析日期，默认为系统时区
+}
+
+export interface SessionPage {
+  sessions: SessionDetail[];
+  next_cursor?: string;
+}
+
+export interface ResolveRecoveredSessionRequest {
+  session_id: string;
+  outcome?: SessionOutcome;
+  duration_minutes?: number;
+}
+
+export interface PomodoroState {
+  task_id: string;
+  completed_focus_sessions: number;
+  long_break_every: number;
+  next_kind: SessionKind;
+  next_minutes: number;
 }
 
 export interface StatisticsRequest {
   start_date: string;
   end_date: string;
-  group_by: 'Task' | 'TaskGroup';
+  group_by: 'Task' | 'TaskGroup' | 'Tag';
+  include_manual?: boolean;
+  timezone?: string; // IANA name; defaults to the system timezone
 }
 
 export interface TaskStatistics {
   task_id: string;
   task_name: string;
   task_group_name: string;
+  task_group_color?: string;
   total_sessions: number;
   total_duration_minutes: number;
+  total_duration_seconds: number;
   completed_sessions: number;
+  abandoned_sessions: number;
+  interrupted_sessions: number;
+  auto_closed_sessions: number;
+  manual_sessions: number;
   completion_rate: number;
 }
 
 export interface TaskGroupStatistics {
   task_group_id: string;
   task_group_name: string;
+  parent_id?: string;
+  color?: string;
+  total_tasks: number;
+  total_sessions: number;
+  total_duration_minutes: number;
+  total_duration_seconds: number;
+  completed_sessions: number;
+  abandoned_sessions: number;
+  interrupted_sessions: number;
+  auto_closed_sessions: number;
+  manual_sessions: number;
+  completion_rate: number;
+  // 自身加上所有子任务组的合计
+  rollup_tasks: number;
+  rollup_sessions: number;
+  rollup_duration_minutes: number;
+  rollup_duration_seconds: number;
+  rollup_completed_sessions: number;
+  rollup_completion_rate: number;
+}
+
+// 带多个标签的任务会计入每个标签
+export interface TagStatistics {
+  tag_id: string;
+  tag_name: string;
   total_tasks: number;
   total_sessions: number;
   total_duration_minutes: number;
+  total_duration_seconds: number;
   completed_sessions: number;
+  abandoned_sessions: number;
+  interrupted_sessions: number;
+  auto_closed_sessions: number;
+  manual_sessions: number;
   completion_rate: number;
 }
 
 export interface StatisticsResponse {
   task_statistics: TaskStatistics[];
   task_group_statistics: TaskGroupStatistics[];
+  tag_statistics: TagStatistics[];
+}
+
+export interface TaskEffort {
+  task_id: string;
+  estimate_minutes?: number;
+  total_sessions: number;
+  actual_minutes: number;
+  actual_seconds: number;
+  remaining_minutes?: number; // 超出预估后为负数
+  ratio?: number;
+}
+
+export interface EstimateReportRequest {
+  start_date: string;
+  end_date: string;
+  include_manual?: boolean;
+  timezone?: string;
+}
+
+export interface TaskEstimateAccuracy {
+  task_id: string;
+  task_name: string;
+  task_group_id: string;
+  task_group_name: string;
+  status: TaskStatus;
+  estimate_minutes: number;
+  actual_minutes: number;
+  actual_seconds: number;
+  period_seconds: number;
+  variance_minutes: number;
+  ratio: number; // 大于 1 表示超出预估
+}
+
+export interface TaskGroupEstimateAccuracy {
+  task_group_id: string;
+  task_group_name: string;
+  estimated_tasks: number;
+  over_estimate_tasks: number;
+  under_estimate_tasks: number;
+  estimate_minutes: number;
+  actual_minutes: number;
+  actual_seconds: number;
+  ratio: number;
+  over_ratio: number;
+  under_ratio: number;
+}
+
+export interface EstimateReport {
+  tasks: TaskEstimateAccuracy[];
+  task_groups: TaskGroupEstimateAccuracy[];
+}
+
+export interface GoalProgressRequest {
+  goal_id?: string; // 默认返回全部目标
+  history_periods?: number; // 默认 7
+  include_manual?: boolean;
+}
+
+export interface GoalPeriodProgress {
+  period_start: string; // YYYY-MM-DD，周目标为周一
+  focused_minutes: number;
+  focused_seconds: number;
+  target_minutes: number;
+  progress: number; // 可能超过 1
+  met: boolean;
+}
+
+export interface GoalProgress {
+  goal: Goal;
+  scope_name: string;
+  current?: GoalPeriodProgress; // 每日目标在不计的日子里为空
+  history: GoalPeriodProgress[]; // 最近的在前
+}
+
+// 'goals://met' 事件的内容
+export interface GoalMet {
+  goal: Goal;
+  scope_name: string;
+  session_id: string;
+  period: GoalPeriodProgress;
+}
+
+export type TimeBucket = 'Day' | 'Week' | 'Month';
+
+export interface TimeSeriesRequest {
+  start_date: string;
+  end_date: string;
+  bucket: TimeBucket;
+  by_task_group?: boolean;
+  include_manual?: boolean;
+  timezone?: string;
+}
+
+export interface TaskGroupSeriesValue {
+  task_group_id: string;
+  task_group_name: string;
+  task_group_color?: string;
+  total_sessions: number;
+  total_duration_minutes: number;
+  total_duration_seconds: number;
+}
+
+export interface TimeSeriesPoint {
+  bucket_start: string; // YYYY-MM-DD
+  total_sessions: number;
+  total_duration_minutes: number;
+  total_duration_seconds: number;
+  task_groups: TaskGroupSeriesValue[];
+}
+
+export interface TimeSeriesResponse {
+  bucket: TimeBucket;
+  points: TimeSeriesPoint[];
+}
+
+export type AppErrorCode = 'NotFound' | 'Validation' | 'Conflict' | 'Database' | 'Corruption';
+
+// 后端命令失败时返回的结构化错误
+export interface AppError {
+  code: AppErrorCode;
+  message: string;
+  details: Record<string, unknown> | null;
 }
 
 export type TimerMode = 'countdown' | 'forward';
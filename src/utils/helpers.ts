@@ -1,5 +1,6 @@
 import { format, startOfDay, endOfDay, subDays, startOfWeek, endOfWeek, startOfMonth, endOfMonth } from 'date-fns';
 import { zhCN } from 'date-fns/locale';
+import type { AppError } from '../types';
 
 export function formatDuration(minutes: number, language: 'zh' | 'en' = 'zh'): string {
   const hours = Math.floor(minutes / 60);
@@ -74,3 +75,11 @@ export function calculateCompletionRate(completed: number, total: number): numbe
   if (total === 0) return 0;
   return Math.round((completed / total) * 100);
 }
+
+export function isAppError(error: unknown): error is AppError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+export function getErrorMessage(error: unknown): string {
+  return isAppError(error) ? error.message : String(error);
+}